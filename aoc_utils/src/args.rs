@@ -0,0 +1,130 @@
+use anyhow::{anyhow, Result};
+use std::env;
+use std::fmt;
+use std::iter::Skip;
+use std::str::FromStr;
+use std::vec;
+
+/// Command line arguments: flags, which may be followed by values, and positional arguments.
+/// Iterating yields each flag or positional argument, after which `value` and `parse` take the
+/// values which follow it:
+///
+/// ```ignore
+/// let mut args = aoc_utils::args();
+/// while let Some(arg) = args.next() {
+///     match arg.as_str() {
+///         "--plot" => options.plot = Some(args.parse()?),
+///         "--verbose" => options.verbose = true,
+///         _ => return Err(args.unexpected()),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Args<I> {
+    inner: I,
+    current: Option<String>,
+}
+
+/// The arguments the program was run with, without the program name.
+pub fn args() -> Args<Skip<env::Args>> {
+    Args::new(env::args().skip(1))
+}
+
+impl Args<vec::IntoIter<String>> {
+    /// Arguments split from a line on whitespace, e.g. to parse options in tests.
+    pub fn from_line(line: &str) -> Self {
+        Args::new(
+            line.split_whitespace()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .into_iter(),
+        )
+    }
+}
+
+impl<I: Iterator<Item = String>> Args<I> {
+    pub fn new(inner: I) -> Self {
+        Self {
+            inner,
+            current: None,
+        }
+    }
+
+    fn current(&self) -> &str {
+        self.current.as_deref().unwrap_or("arguments")
+    }
+
+    /// The next value for the current flag.
+    pub fn value(&mut self) -> Result<String> {
+        self.inner
+            .next()
+            .ok_or_else(|| anyhow!("Missing value for {}", self.current()))
+    }
+
+    /// The next value for the current flag, parsed.
+    pub fn parse<T>(&mut self) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = self.value()?;
+        T::from_str(&value)
+            .map_err(|e| anyhow!("Invalid value for {} '{}': {}", self.current(), value, e))
+    }
+
+    /// The error for an argument which isn't recognized.
+    pub fn unexpected(&self) -> anyhow::Error {
+        anyhow!("Unexpected argument: {}", self.current())
+    }
+}
+
+impl<I: Iterator<Item = String>> Iterator for Args<I> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.current = self.inner.next();
+        self.current.clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_args() -> Result<()> {
+        let mut args = Args::from_line("--size 3 7 --fast input.txt --bogus");
+
+        assert_eq!(Some("--size"), args.next().as_deref());
+        assert_eq!(3, args.parse::<u32>()?);
+        assert_eq!("7", args.value()?);
+
+        assert_eq!(Some("--fast"), args.next().as_deref());
+        assert_eq!(Some("input.txt"), args.next().as_deref());
+
+        assert_eq!(Some("--bogus"), args.next().as_deref());
+        assert_eq!(
+            "Unexpected argument: --bogus",
+            args.unexpected().to_string()
+        );
+        assert_eq!(
+            "Missing value for --bogus",
+            args.value().unwrap_err().to_string()
+        );
+
+        assert_eq!(None, args.next());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut args = Args::from_line("--size x");
+
+        args.next();
+        assert_eq!(
+            "Invalid value for --size 'x': invalid digit found in string",
+            args.parse::<u32>().unwrap_err().to_string()
+        );
+    }
+}
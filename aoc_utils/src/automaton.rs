@@ -0,0 +1,126 @@
+/// A simulation which advances in discrete steps. Implementors provide a single step and keep
+/// their own step counter; driving the simulation forward is shared.
+pub trait Automaton {
+    fn step(&mut self);
+
+    fn step_ct(&self) -> usize;
+
+    /// Whether the simulation has finished, after which stepping does nothing. Simulations which
+    /// can always take another step never finish.
+    fn is_done(&self) -> bool {
+        false
+    }
+
+    /// Takes up to `n` steps, stopping early if the simulation finishes, and returns the number
+    /// of steps taken.
+    fn step_n(&mut self, n: usize) -> usize {
+        for i in 0..n {
+            if self.is_done() {
+                return i;
+            }
+            self.step();
+        }
+
+        n
+    }
+
+    /// Steps until `predicate` holds after a step, returning the step count at that point, or
+    /// `None` if the simulation finishes first.
+    fn step_until<P>(&mut self, mut predicate: P) -> Option<usize>
+    where
+        P: FnMut(&Self) -> bool,
+    {
+        while !self.is_done() {
+            self.step();
+            if predicate(self) {
+                return Some(self.step_ct());
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default)]
+    struct Counter {
+        value: u32,
+        step_ct: usize,
+    }
+
+    impl Automaton for Counter {
+        fn step(&mut self) {
+            self.step_ct += 1;
+            self.value += 3;
+        }
+
+        fn step_ct(&self) -> usize {
+            self.step_ct
+        }
+    }
+
+    /// Counts down to zero, then finishes.
+    struct Countdown {
+        value: u32,
+        step_ct: usize,
+    }
+
+    impl Automaton for Countdown {
+        fn step(&mut self) {
+            if self.value > 0 {
+                self.step_ct += 1;
+                self.value -= 1;
+            }
+        }
+
+        fn step_ct(&self) -> usize {
+            self.step_ct
+        }
+
+        fn is_done(&self) -> bool {
+            self.value == 0
+        }
+    }
+
+    #[test]
+    fn test_step_n() {
+        let mut counter = Counter::default();
+
+        assert_eq!(4, counter.step_n(4));
+        assert_eq!(12, counter.value);
+        assert_eq!(4, counter.step_ct());
+
+        assert_eq!(0, counter.step_n(0));
+        assert_eq!(4, counter.step_ct());
+    }
+
+    #[test]
+    fn test_step_until() {
+        let mut counter = Counter::default();
+
+        assert_eq!(Some(4), counter.step_until(|c| c.value > 10));
+        assert_eq!(12, counter.value);
+
+        // The predicate is only checked after stepping
+        assert_eq!(Some(5), counter.step_until(|c| c.value > 10));
+    }
+
+    #[test]
+    fn test_done() {
+        let mut countdown = Countdown {
+            value: 3,
+            step_ct: 0,
+        };
+
+        assert_eq!(Some(1), countdown.step_until(|c| c.value == 2));
+        assert_eq!(None, countdown.step_until(|c| c.value > 5));
+        assert_eq!(3, countdown.step_ct());
+        assert!(countdown.is_done());
+
+        assert_eq!(0, countdown.step_n(10));
+        assert_eq!(3, countdown.step_ct());
+    }
+}
@@ -0,0 +1,297 @@
+use crate::Automaton;
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+/// Guards `step` and `until` against running practically forever, e.g. when a condition never
+/// becomes true.
+const MAX_STEPS: usize = 1_000_000;
+
+/// A simulation which can be driven interactively by `debug_repl`. Days only need to implement
+/// the pieces which make sense for their state.
+pub trait Debuggable: Automaton + Clone + fmt::Debug {
+    fn get(&self, _x: usize, _y: usize) -> Result<String> {
+        Err(anyhow!("get is not supported"))
+    }
+
+    fn set(&mut self, _x: usize, _y: usize, _value: &str) -> Result<()> {
+        Err(anyhow!("set is not supported"))
+    }
+
+    /// Evaluates a day-specific condition, e.g. `all_flashed`, for `until`.
+    fn condition(&self, condition: &str) -> Result<bool> {
+        Err(anyhow!("Unknown condition: '{}'", condition))
+    }
+}
+
+const HELP: &str = "\
+step [n]        advance one (or n) steps
+until <cond>    step until the condition holds
+show            print the current state
+get <x> <y>     print the value at (x, y)
+set <x> <y> <v> set the value at (x, y)
+undo            revert the last step, until or set
+quit            exit the debugger";
+
+fn arg<T>(args: &[&str], i: usize, name: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let arg = args.get(i).ok_or_else(|| anyhow!("Missing {}", name))?;
+    T::from_str(arg).map_err(|e| anyhow!("Invalid {} '{}': {}", name, arg, e))
+}
+
+struct Session<T> {
+    state: T,
+    history: Vec<T>,
+}
+
+impl<T: Debuggable> Session<T> {
+    fn until(&mut self, condition: &str) -> Result<()> {
+        let prev = self.state.clone();
+
+        for _ in 0..MAX_STEPS {
+            if self.state.is_done() {
+                self.state = prev;
+                return Err(anyhow!(
+                    "'{}' did not hold before the simulation finished",
+                    condition
+                ));
+            }
+
+            self.state.step();
+
+            match self.state.condition(condition) {
+                Ok(true) => {
+                    self.history.push(prev);
+                    return Ok(());
+                }
+                Ok(false) => (),
+                Err(e) => {
+                    self.state = prev;
+                    return Err(e);
+                }
+            }
+        }
+
+        self.state = prev;
+        Err(anyhow!(
+            "'{}' did not hold within {} steps",
+            condition,
+            MAX_STEPS
+        ))
+    }
+
+    /// Runs a single command, returning false once the session should end.
+    fn execute<W: Write>(&mut self, args: &[&str], output: &mut W) -> Result<bool> {
+        match args.first() {
+            None => (),
+            Some(&"quit") | Some(&"exit") => return Ok(false),
+            Some(&"help") => writeln!(output, "{}", HELP)?,
+            Some(&"show") => write!(output, "{:?}", self.state)?,
+            Some(&"step") => {
+                let n = if args.len() > 1 {
+                    arg(args, 1, "step count")?
+                } else {
+                    1
+                };
+                if n > MAX_STEPS {
+                    return Err(anyhow!("Can step at most {} times at once", MAX_STEPS));
+                }
+
+                self.history.push(self.state.clone());
+                if self.state.step_n(n) < n {
+                    writeln!(output, "step {} (finished)", self.state.step_ct())?;
+                } else {
+                    writeln!(output, "step {}", self.state.step_ct())?;
+                }
+            }
+            Some(&"until") => {
+                self.until(&args[1..].join(" "))?;
+                writeln!(output, "step {}", self.state.step_ct())?;
+            }
+            Some(&"get") => {
+                let value = self.state.get(arg(args, 1, "x")?, arg(args, 2, "y")?)?;
+                writeln!(output, "{}", value)?;
+            }
+            Some(&"set") => {
+                let (x, y, value) = (
+                    arg(args, 1, "x")?,
+                    arg(args, 2, "y")?,
+                    arg::<String>(args, 3, "value")?,
+                );
+
+                let prev = self.state.clone();
+                self.state.set(x, y, &value)?;
+                self.history.push(prev);
+            }
+            Some(&"undo") => {
+                self.state = self
+                    .history
+                    .pop()
+                    .ok_or_else(|| anyhow!("Nothing to undo"))?;
+                writeln!(output, "step {}", self.state.step_ct())?;
+            }
+            Some(command) => return Err(anyhow!("Unknown command: '{}', try 'help'", command)),
+        }
+
+        Ok(true)
+    }
+}
+
+/// Reads commands from `input` until it's exhausted or `quit` is entered, returning the final
+/// state.
+pub fn debug_repl<T, R, W>(state: T, input: R, mut output: W) -> Result<T>
+where
+    T: Debuggable,
+    R: BufRead,
+    W: Write,
+{
+    let mut session = Session {
+        state,
+        history: Vec::new(),
+    };
+
+    write!(output, "> ")?;
+    output.flush()?;
+
+    for line in input.lines() {
+        let line = line?;
+        let args = line.split_ascii_whitespace().collect::<Vec<_>>();
+
+        match session.execute(&args, &mut output) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(e) => writeln!(output, "error: {}", e)?,
+        }
+
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+
+    Ok(session.state)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[derive(Debug, Default, Clone)]
+    struct Counter {
+        values: Vec<u32>,
+        step_ct: usize,
+    }
+
+    impl Automaton for Counter {
+        fn step(&mut self) {
+            self.step_ct += 1;
+            self.values.iter_mut().for_each(|v| *v += 1);
+        }
+
+        fn step_ct(&self) -> usize {
+            self.step_ct
+        }
+
+        fn is_done(&self) -> bool {
+            self.values[1] >= 20
+        }
+    }
+
+    impl Debuggable for Counter {
+        fn get(&self, x: usize, _y: usize) -> Result<String> {
+            self.values
+                .get(x)
+                .map(|v| v.to_string())
+                .ok_or_else(|| anyhow!("Out of bounds: {}", x))
+        }
+
+        fn set(&mut self, x: usize, _y: usize, value: &str) -> Result<()> {
+            *self
+                .values
+                .get_mut(x)
+                .ok_or_else(|| anyhow!("Out of bounds: {}", x))? = u32::from_str(value)?;
+            Ok(())
+        }
+
+        fn condition(&self, condition: &str) -> Result<bool> {
+            let (_, min) = condition
+                .split_once("min ")
+                .ok_or_else(|| anyhow!("Unknown condition: '{}'", condition))?;
+            Ok(self.values[0] >= u32::from_str(min)?)
+        }
+    }
+
+    fn run(commands: &str) -> Result<(Counter, String)> {
+        let counter = Counter {
+            values: vec![0, 10],
+            step_ct: 0,
+        };
+
+        let mut output = Vec::new();
+        let counter = debug_repl(counter, Cursor::new(commands), &mut output)?;
+
+        Ok((counter, String::from_utf8(output)?))
+    }
+
+    #[test]
+    fn test_step_and_undo() -> Result<()> {
+        let (counter, output) = run("step\nstep 3\nget 1 0\nundo\nget 1 0\n")?;
+
+        assert_eq!(1, counter.step_ct);
+        assert_eq!(vec![1, 11], counter.values);
+        assert_eq!("> step 1\n> step 4\n> 14\n> step 1\n> 11\n> ", output);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_until_and_set() -> Result<()> {
+        let (counter, output) = run("set 0 0 5\nuntil min 8\nuntil bogus\nundo\nundo\nquit\nstep")?;
+
+        assert_eq!(0, counter.step_ct);
+        assert_eq!(vec![0, 10], counter.values);
+        assert_eq!(
+            "> > step 3\n> error: Unknown condition: 'bogus'\n> step 0\n> step 0\n> ",
+            output
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_finished() -> Result<()> {
+        let (counter, output) = run("step 4\nuntil min 100\nstep 15\n")?;
+
+        assert_eq!(10, counter.step_ct);
+        assert_eq!(
+            "> step 4\n\
+             > error: 'min 100' did not hold before the simulation finished\n\
+             > step 10 (finished)\n\
+             > ",
+            output
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<()> {
+        let (_, output) = run("get 5 0\nset 0\nstep x\nstep 18446744073709551615\nundo\nfly\n")?;
+
+        assert_eq!(
+            "> error: Out of bounds: 5\n\
+             > error: Missing y\n\
+             > error: Invalid step count 'x': invalid digit found in string\n\
+             > error: Can step at most 1000000 times at once\n\
+             > error: Nothing to undo\n\
+             > error: Unknown command: 'fly', try 'help'\n\
+             > ",
+            output
+        );
+
+        Ok(())
+    }
+}
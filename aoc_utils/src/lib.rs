@@ -2,9 +2,23 @@ use anyhow::{anyhow, Result};
 use env_logger::Target;
 use log::LevelFilter;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::BufReader;
 use std::path::Path;
 
+mod args;
+mod automaton;
+mod debugger;
+mod lines;
+mod memo;
+mod snapshot;
+
+pub use args::{args, Args};
+pub use automaton::Automaton;
+pub use debugger::{debug_repl, Debuggable};
+pub use lines::{lines, Lines};
+pub use memo::{Memo, MemoStats};
+pub use snapshot::{assert_snapshot, UPDATE_SNAPSHOTS_VAR};
+
 const INPUT_PATH: &str = "input/input.txt";
 const TEST_INPUT_PATH: &str = "input/test.txt";
 
@@ -34,16 +48,21 @@ pub fn test_input() -> Result<Vec<String>> {
     read_lines(TEST_INPUT_PATH)
 }
 
-fn read_lines(path: &'static str) -> Result<Vec<String>> {
-    let lines: Vec<_> = BufReader::new(File::open(&Path::new(path))?)
-        .lines()
-        .filter_map(|l| l.ok())
-        .collect();
+pub fn input_lines() -> Result<Lines<BufReader<File>>> {
+    lines(INPUT_PATH)
+}
+
+pub fn test_input_lines() -> Result<Lines<BufReader<File>>> {
+    lines(TEST_INPUT_PATH)
+}
+
+pub fn read_lines<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+    let lines = lines(&path)?.collect::<Result<Vec<_>>>()?;
 
     if !lines.is_empty() {
         Ok(lines)
     } else {
-        Err(anyhow!("No input: {}", path))
+        Err(anyhow!("No input: {}", path.as_ref().display()))
     }
 }
 
@@ -0,0 +1,128 @@
+use anyhow::{anyhow, Result};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// Streams lines from a reader, without line endings (`\n` or `\r\n`). Blank lines at the end of
+/// the input are dropped. The first read error is reported with its line number and ends the
+/// stream.
+pub struct Lines<R> {
+    inner: io::Lines<R>,
+    source: Option<String>,
+    line_no: usize,
+    pending_blank: VecDeque<String>,
+    done: bool,
+}
+
+impl<R: BufRead> Lines<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            inner: reader.lines(),
+            source: None,
+            line_no: 0,
+            pending_blank: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    fn with_source(mut self, source: String) -> Self {
+        self.source = Some(source);
+        self
+    }
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Blank lines are held back until we know they're followed by something
+        if let Some(line) = self.pending_blank.pop_front() {
+            return Some(Ok(line));
+        }
+
+        if self.done {
+            return None;
+        }
+
+        loop {
+            self.line_no += 1;
+
+            match self.inner.next() {
+                Some(Ok(line)) if line.trim().is_empty() => self.pending_blank.push_back(line),
+                Some(Ok(line)) => {
+                    if let Some(blank) = self.pending_blank.pop_front() {
+                        self.pending_blank.push_back(line);
+                        return Some(Ok(blank));
+                    }
+                    return Some(Ok(line));
+                }
+                Some(Err(e)) => {
+                    self.done = true;
+                    self.pending_blank.clear();
+
+                    let err = match &self.source {
+                        Some(source) => anyhow!("{}, line {}: {}", source, self.line_no, e),
+                        None => anyhow!("Line {}: {}", self.line_no, e),
+                    };
+                    return Some(Err(err));
+                }
+                None => {
+                    self.done = true;
+                    self.pending_blank.clear();
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+pub fn lines<P: AsRef<Path>>(path: P) -> Result<Lines<BufReader<File>>> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+
+    Ok(Lines::new(BufReader::new(file)).with_source(path.display().to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    fn collect(input: &[u8]) -> Result<Vec<String>> {
+        Lines::new(Cursor::new(input.to_vec())).collect()
+    }
+
+    #[test]
+    fn test_line_endings() -> Result<()> {
+        assert_eq!(vec!["a", "b", "c"], collect(b"a\r\nb\nc")?);
+        assert_eq!(vec!["a", "b"], collect(b"a\r\nb\r\n")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_blank_lines() -> Result<()> {
+        assert_eq!(vec!["a", "", " ", "b"], collect(b"a\n\n \nb\n\n\r\n \n")?);
+        assert_eq!(vec!["", "a"], collect(b"\na")?);
+        assert!(collect(b"\n\n")?.is_empty());
+        assert!(collect(b"")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let mut lines = Lines::new(Cursor::new(b"a\n\nb\xff\nc\n".to_vec()));
+
+        assert_eq!("a", lines.next().unwrap().unwrap());
+        let err = lines.next().unwrap().unwrap_err();
+        assert!(err.to_string().starts_with("Line 3:"), "{}", err);
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn test_missing_file() {
+        assert!(lines("does/not/exist.txt").is_err());
+    }
+}
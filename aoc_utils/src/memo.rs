@@ -0,0 +1,152 @@
+use log::debug;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct MemoStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl MemoStats {
+    pub fn lookups(&self) -> usize {
+        self.hits + self.misses
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        if self.lookups() == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.lookups() as f64
+        }
+    }
+}
+
+impl fmt::Display for MemoStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hits={}, misses={}, hit_rate={:.1}%",
+            self.hits,
+            self.misses,
+            100.0 * self.hit_rate()
+        )
+    }
+}
+
+/// A cache for recursive solvers. The compute function is handed the memo itself so that it can
+/// recurse through `get_or_compute` for its sub-problems.
+#[derive(Debug)]
+pub struct Memo<K, V> {
+    cache: HashMap<K, V>,
+    stats: MemoStats,
+}
+
+impl<K, V> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self {
+            cache: HashMap::new(),
+            stats: MemoStats::default(),
+        }
+    }
+}
+
+impl<K, V> Memo<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_or_compute<F>(&mut self, key: K, compute: F) -> V
+    where
+        F: FnOnce(&mut Self, &K) -> V,
+    {
+        if let Some(value) = self.cache.get(&key) {
+            self.stats.hits += 1;
+            return value.clone();
+        }
+
+        self.stats.misses += 1;
+
+        // The cache can't be borrowed while computing, since the computation may recurse into it
+        let value = compute(self, &key);
+        self.cache.insert(key, value.clone());
+
+        value
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.cache.get(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.stats = MemoStats::default();
+    }
+
+    pub fn stats(&self) -> MemoStats {
+        self.stats
+    }
+
+    pub fn log_stats(&self, name: &str) {
+        debug!("{} memo: size={}, {}", name, self.len(), self.stats);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fib(memo: &mut Memo<u64, u64>, n: u64) -> u64 {
+        memo.get_or_compute(n, |memo, n| {
+            if *n < 2 {
+                *n
+            } else {
+                fib(memo, n - 1) + fib(memo, n - 2)
+            }
+        })
+    }
+
+    #[test]
+    fn test_recursive() {
+        let mut memo = Memo::new();
+
+        assert_eq!(12586269025, fib(&mut memo, 50));
+        assert_eq!(51, memo.len());
+        assert_eq!(
+            MemoStats {
+                hits: 48,
+                misses: 51
+            },
+            memo.stats()
+        );
+
+        // Everything is cached now
+        assert_eq!(12586269025, fib(&mut memo, 50));
+        assert_eq!(49, memo.stats().hits);
+        assert_eq!(51, memo.stats().misses);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut memo = Memo::new();
+        fib(&mut memo, 10);
+        assert!(!memo.is_empty());
+
+        memo.clear();
+        assert!(memo.is_empty());
+        assert_eq!(MemoStats::default(), memo.stats());
+    }
+}
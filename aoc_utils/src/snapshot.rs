@@ -0,0 +1,128 @@
+use anyhow::{anyhow, Result};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// When set (to anything other than `0`), mismatched or missing snapshots are rewritten instead of
+/// failing.
+pub const UPDATE_SNAPSHOTS_VAR: &str = "UPDATE_SNAPSHOTS";
+
+const SNAPSHOT_DIR: &str = "snapshots";
+
+/// Compares a rendering against `snapshots/<name>.snap`, relative to the crate being tested.
+pub fn assert_snapshot<S: AsRef<str>>(name: &str, actual: S) -> Result<()> {
+    let update = env::var(UPDATE_SNAPSHOTS_VAR).is_ok_and(|v| !v.is_empty() && v != "0");
+
+    check_snapshot(Path::new(SNAPSHOT_DIR), name, actual.as_ref(), update)
+}
+
+fn snapshot_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.snap", name))
+}
+
+fn check_snapshot(dir: &Path, name: &str, actual: &str, update: bool) -> Result<()> {
+    let path = snapshot_path(dir, name);
+
+    let expected = match fs::read_to_string(&path) {
+        Ok(expected) => expected,
+        Err(_) if update => String::new(),
+        Err(e) => {
+            return Err(anyhow!(
+                "Missing snapshot {} ({}), run with {}=1 to create it",
+                path.display(),
+                e,
+                UPDATE_SNAPSHOTS_VAR
+            ))
+        }
+    };
+
+    if expected == actual {
+        return Ok(());
+    }
+
+    if update {
+        fs::create_dir_all(dir)?;
+        fs::write(&path, actual)?;
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "Snapshot {} does not match, run with {}=1 to update it:\n{}",
+        path.display(),
+        UPDATE_SNAPSHOTS_VAR,
+        diff(&expected, actual)
+    ))
+}
+
+fn diff(expected: &str, actual: &str) -> String {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+
+    let mut out = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        let (e, a) = (expected.get(i), actual.get(i));
+        if e != a {
+            if let Some(e) = e {
+                out += &format!("{:>4} - {}\n", i + 1, e);
+            }
+            if let Some(a) = a {
+                out += &format!("{:>4} + {}\n", i + 1, a);
+            }
+        }
+    }
+
+    // Only trailing newlines differ
+    if out.is_empty() {
+        out += "(whitespace at end of input differs)\n";
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!(
+            "aoc_utils_snapshot_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_missing() -> Result<()> {
+        let dir = temp_dir("missing");
+
+        assert!(check_snapshot(&dir, "grid", "#.\n.#\n", false).is_err());
+
+        check_snapshot(&dir, "grid", "#.\n.#\n", true)?;
+        check_snapshot(&dir, "grid", "#.\n.#\n", false)?;
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_mismatch() -> Result<()> {
+        let dir = temp_dir("mismatch");
+
+        check_snapshot(&dir, "grid", "#.\n.#\n", true)?;
+
+        let err = check_snapshot(&dir, "grid", "#.\n##\n", false).unwrap_err();
+        assert!(
+            err.to_string().ends_with("   2 - .#\n   2 + ##\n"),
+            "{}",
+            err
+        );
+
+        check_snapshot(&dir, "grid", "#.\n##\n", true)?;
+        check_snapshot(&dir, "grid", "#.\n##\n", false)?;
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
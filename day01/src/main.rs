@@ -1,58 +1,312 @@
-use anyhow::Result;
-use std::fs::File;
-use std::io::{prelude::*, BufReader};
-use std::path::Path;
-
-const PATH: &str = "input/day01.txt";
-
-fn _a() -> Result<()> {
-    let path = Path::new(PATH);
-
-    let mut increases = 0;
-    let mut prev = None;
-    for line in BufReader::new(File::open(&path)?).lines() {
-        let depth: u32 = line?.parse()?;
-        if let Some(prev) = prev {
-            if depth > prev {
-                increases += 1;
+use anyhow::{anyhow, Result};
+use log::{debug, warn, LevelFilter};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+const PART_ONE_WINDOW: usize = 1;
+const PART_TWO_WINDOW: usize = 3;
+
+/// Counts increases between consecutive sums of `size` readings. Consecutive windows share all
+/// but one reading, so comparing the sums only requires comparing the reading entering the window
+/// against the one leaving it.
+#[derive(Debug)]
+struct Sonar {
+    size: usize,
+    window: VecDeque<u32>,
+    reading_ct: usize,
+    increase_ct: usize,
+    /// Only kept when asked for, as a long-running monitor would otherwise grow without bound.
+    increases: Option<Vec<usize>>,
+}
+
+impl Sonar {
+    fn new(size: usize) -> Result<Self> {
+        if size == 0 {
+            return Err(anyhow!("Window size must be positive"));
+        }
+
+        Ok(Self {
+            size,
+            window: VecDeque::with_capacity(size + 1),
+            reading_ct: 0,
+            increase_ct: 0,
+            increases: None,
+        })
+    }
+
+    /// Also records the index of every reading which completes a larger window.
+    fn record_indices(mut self) -> Self {
+        self.increases = Some(Vec::new());
+        self
+    }
+
+    /// Records a reading, returning whether it completed a window with a larger sum than the
+    /// previous window.
+    fn push(&mut self, depth: u32) -> bool {
+        let idx = self.reading_ct;
+        self.reading_ct += 1;
+
+        self.window.push_back(depth);
+        if self.window.len() <= self.size {
+            return false;
+        }
+
+        let leaving = self.window.pop_front().unwrap();
+        if depth > leaving {
+            self.increase_ct += 1;
+            if let Some(increases) = self.increases.as_mut() {
+                increases.push(idx);
             }
+            true
+        } else {
+            false
         }
-        prev = Some(depth);
     }
 
-    println!("{}", increases);
+    fn increase_ct(&self) -> usize {
+        self.increase_ct
+    }
 
-    Ok(())
+    /// Indices of the readings which completed a larger window, if they're being recorded.
+    fn increases(&self) -> Option<&[usize]> {
+        self.increases.as_deref()
+    }
 }
 
-fn main() -> Result<()> {
-    let path = Path::new(PATH);
+/// Running statistics over a stream of readings, for any number of window sizes.
+struct Monitor {
+    sonars: Vec<Sonar>,
+    last: Option<u32>,
+    min: Option<u32>,
+    max: Option<u32>,
+    run: usize,
+    longest_run: usize,
+}
 
-    let mut increases = 0;
+impl Monitor {
+    fn new(sonars: Vec<Sonar>) -> Self {
+        Self {
+            sonars,
+            last: None,
+            min: None,
+            max: None,
+            run: 0,
+            longest_run: 0,
+        }
+    }
 
-    let mut prev: Option<Vec<u32>> = None;
-    let mut curr = vec![];
-    for line in BufReader::new(File::open(&path)?).lines() {
-        let depth: u32 = line?.parse()?;
-        curr.push(depth);
-        if curr.len() > 3 {
-            curr = curr.split_off(1);
+    fn push(&mut self, depth: u32) {
+        for sonar in self.sonars.iter_mut() {
+            sonar.push(depth);
         }
 
-        if curr.len() == 3 {
-            let curr_sum: u32 = curr.iter().sum();
-            if let Some(prev) = &prev {
-                let prev_sum: u32 = prev.iter().sum();
+        self.run = match self.last {
+            Some(last) if depth > last => self.run + 1,
+            _ => 1,
+        };
+        self.longest_run = self.longest_run.max(self.run);
+
+        self.last = Some(depth);
+        self.min = Some(self.min.map_or(depth, |m| m.min(depth)));
+        self.max = Some(self.max.map_or(depth, |m| m.max(depth)));
+    }
+}
 
-                if curr_sum > prev_sum {
-                    increases += 1;
-                }
+impl fmt::Display for Monitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(last), Some(min), Some(max)) = (self.last, self.min, self.max) {
+            write!(f, "depth={} min={} max={}", last, min, max)?;
+        }
+
+        write!(f, " run={} longest_run={}", self.run, self.longest_run)?;
+
+        for sonar in self.sonars.iter() {
+            write!(f, " window{}={}", sonar.size, sonar.increase_ct())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_depth(line: &str) -> Result<u32> {
+    u32::from_str(line.trim()).map_err(|e| anyhow!("Invalid depth '{}': {}", line, e))
+}
+
+struct Options {
+    live: bool,
+    window_sizes: Vec<usize>,
+}
+
+impl Options {
+    /// Parses `[live] [SIZE]...`, defaulting to the window sizes of both parts.
+    fn parse<I: Iterator<Item = String>>(mut args: aoc_utils::Args<I>) -> Result<Self> {
+        let mut options = Self {
+            live: false,
+            window_sizes: Vec::new(),
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "live" => options.live = true,
+                _ if !arg.starts_with("--") => options.window_sizes.push(
+                    usize::from_str(&arg)
+                        .map_err(|e| anyhow!("Invalid window size '{}': {}", arg, e))?,
+                ),
+                _ => return Err(args.unexpected()),
             }
+        }
 
-            prev = Some(curr.clone());
+        if options.window_sizes.is_empty() {
+            options.window_sizes = vec![PART_ONE_WINDOW, PART_TWO_WINDOW];
         }
+
+        Ok(options)
+    }
+}
+
+/// Reports running statistics after every reading from stdin, e.g. at the end of `tail -f`.
+fn live(sonars: Vec<Sonar>) -> Result<()> {
+    let mut monitor = Monitor::new(sonars);
+    let mut stdout = io::stdout();
+
+    for line in aoc_utils::Lines::new(io::stdin().lock()) {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // A single bad reading shouldn't take down a long-running monitor
+        match parse_depth(&line) {
+            Ok(depth) => monitor.push(depth),
+            Err(e) => {
+                warn!("{}", e);
+                continue;
+            }
+        }
+
+        writeln!(stdout, "{}", monitor)?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    aoc_utils::init_logger(LevelFilter::Info)?;
+
+    let options = Options::parse(aoc_utils::args())?;
+
+    let sonars = options
+        .window_sizes
+        .into_iter()
+        .map(Sonar::new)
+        .collect::<Result<Vec<_>>>()?;
+
+    if options.live {
+        return live(sonars);
+    }
+
+    let mut sonars = sonars
+        .into_iter()
+        .map(Sonar::record_indices)
+        .collect::<Vec<_>>();
+
+    for line in aoc_utils::input_lines()? {
+        let depth = parse_depth(&line?)?;
+        for sonar in sonars.iter_mut() {
+            sonar.push(depth);
+        }
+    }
+
+    for sonar in sonars.iter() {
+        debug!(
+            "Window {}: {:?}",
+            sonar.size,
+            sonar.increases().unwrap_or_default()
+        );
+        println!("{}", sonar.increase_ct());
     }
 
-    println!("{}", increases);
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aoc_utils::Args;
+
+    const DEPTHS: [u32; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+    fn analyze(size: usize) -> Result<Sonar> {
+        let mut sonar = Sonar::new(size)?.record_indices();
+        for depth in DEPTHS {
+            sonar.push(depth);
+        }
+        Ok(sonar)
+    }
+
+    #[test]
+    fn test_single_readings() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let sonar = analyze(PART_ONE_WINDOW)?;
+        assert_eq!(7, sonar.increase_ct());
+        assert_eq!(Some(&[1, 2, 3, 5, 6, 7, 9][..]), sonar.increases());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_windows() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let sonar = analyze(PART_TWO_WINDOW)?;
+        assert_eq!(5, sonar.increase_ct());
+        assert_eq!(Some(&[3, 6, 7, 8, 9][..]), sonar.increases());
+
+        // A window covering every reading has nothing to compare against
+        assert_eq!(0, analyze(DEPTHS.len())?.increase_ct());
+        assert!(Sonar::new(0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_monitor() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let mut monitor = Monitor::new(vec![Sonar::new(1)?, Sonar::new(3)?]);
+        for depth in DEPTHS {
+            monitor.push(depth);
+        }
+
+        assert_eq!(4, monitor.longest_run);
+        assert_eq!(
+            "depth=263 min=199 max=269 run=2 longest_run=4 window1=7 window3=5",
+            monitor.to_string()
+        );
+
+        // The monitor only keeps counts
+        assert!(monitor.sonars.iter().all(|s| s.increases().is_none()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_options() -> Result<()> {
+        let options = Options::parse(Args::from_line(""))?;
+        assert!(!options.live);
+        assert_eq!(vec![PART_ONE_WINDOW, PART_TWO_WINDOW], options.window_sizes);
+
+        let options = Options::parse(Args::from_line("live 2 5"))?;
+        assert!(options.live);
+        assert_eq!(vec![2, 5], options.window_sizes);
+
+        assert!(Options::parse(Args::from_line("three")).is_err());
+        assert!(Options::parse(Args::from_line("--live")).is_err());
+
+        Ok(())
+    }
+}
@@ -1,12 +1,18 @@
 use anyhow::{anyhow, Result};
-use std::fs::File;
+use script::Script;
+use std::fmt;
+use std::fs::{self, File};
 use std::io::{prelude::*, BufReader};
 use std::path::Path;
 use std::str::FromStr;
 
+mod planner;
+mod plot;
+mod script;
+
 const PATH: &str = "input/day02.txt";
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Action {
     Horizontal(i32),
     Vertical(i32),
@@ -17,7 +23,7 @@ impl FromStr for Action {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let (action, amt) = s
-            .split_once(" ")
+            .split_once(' ')
             .ok_or_else(|| anyhow!("Failed to parse {}", s))?;
 
         let amt = amt.parse()?;
@@ -34,7 +40,18 @@ impl FromStr for Action {
     }
 }
 
-#[derive(Debug, Default)]
+/// Formats actions as they appear in the input.
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Horizontal(amt) => write!(f, "forward {}", amt),
+            Self::Vertical(amt) if *amt < 0 => write!(f, "up {}", -amt),
+            Self::Vertical(amt) => write!(f, "down {}", amt),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 struct Position {
     x: i32,
     y: i32,
@@ -42,7 +59,7 @@ struct Position {
 }
 
 impl Position {
-    fn _apply_a(&mut self, action: Action) {
+    fn apply_naive(&mut self, action: Action) {
         match action {
             Action::Horizontal(amt) => self.x += amt,
             Action::Vertical(amt) => self.y += amt,
@@ -60,16 +77,291 @@ impl Position {
     }
 }
 
-fn main() -> Result<()> {
-    let path = Path::new(PATH);
+/// How `up` and `down` are interpreted: the naive model changes depth directly, while the aim
+/// model changes the aim which `forward` then applies to depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MovementModel {
+    Naive,
+    Aim,
+}
+
+impl MovementModel {
+    fn apply(self, position: &mut Position, action: Action) {
+        match self {
+            Self::Naive => position.apply_naive(action),
+            Self::Aim => position.apply(action),
+        }
+    }
+
+    /// Like `apply`, but returns `None` instead of overflowing.
+    fn checked_apply(self, position: &Position, action: Action) -> Option<Position> {
+        let mut next = *position;
+        match (self, action) {
+            (Self::Naive, Action::Horizontal(amt)) => next.x = next.x.checked_add(amt)?,
+            (Self::Naive, Action::Vertical(amt)) => next.y = next.y.checked_add(amt)?,
+            (Self::Aim, Action::Horizontal(amt)) => {
+                next.x = next.x.checked_add(amt)?;
+                next.y = next.y.checked_add(next.aim.checked_mul(amt)?)?;
+            }
+            (Self::Aim, Action::Vertical(amt)) => next.aim = next.aim.checked_add(amt)?,
+        }
+        Some(next)
+    }
+}
+
+impl FromStr for MovementModel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if "naive".eq_ignore_ascii_case(s) {
+            Ok(Self::Naive)
+        } else if "aim".eq_ignore_ascii_case(s) {
+            Ok(Self::Aim)
+        } else {
+            Err(anyhow!("Invalid movement model: {}", s))
+        }
+    }
+}
+
+impl fmt::Display for MovementModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Naive => write!(f, "naive"),
+            Self::Aim => write!(f, "aim"),
+        }
+    }
+}
+
+/// Every position the submarine passes through under a given model, starting at the origin.
+#[derive(Debug)]
+struct Course {
+    model: MovementModel,
+    track: Vec<Position>,
+}
+
+impl Course {
+    fn new(model: MovementModel) -> Self {
+        Self {
+            model,
+            track: vec![Position::default()],
+        }
+    }
+
+    fn plot(model: MovementModel, actions: &[Action]) -> Self {
+        let mut course = Self::new(model);
+        for action in actions {
+            course.apply(*action);
+        }
+        course
+    }
+
+    fn apply(&mut self, action: Action) {
+        let mut position = self.position();
+        self.model.apply(&mut position, action);
+        self.track.push(position);
+    }
+
+    fn position(&self) -> Position {
+        *self.track.last().unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlotFormat {
+    Csv,
+    Ascii,
+    Svg,
+}
+
+impl FromStr for PlotFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if "csv".eq_ignore_ascii_case(s) {
+            Ok(Self::Csv)
+        } else if "ascii".eq_ignore_ascii_case(s) {
+            Ok(Self::Ascii)
+        } else if "svg".eq_ignore_ascii_case(s) {
+            Ok(Self::Svg)
+        } else {
+            Err(anyhow!("Invalid plot format: {}", s))
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Options {
+    models: Vec<MovementModel>,
+    plot: Option<PlotFormat>,
+    script: Option<String>,
+    plan: Option<(i32, i32)>,
+    max_magnitude: Option<i32>,
+}
+
+impl Options {
+    /// Parses `[--model naive|aim|both] [--plot csv|ascii|svg] [--script FILE]`, or
+    /// `--plan X DEPTH [--max-magnitude N]`.
+    fn parse<I: Iterator<Item = String>>(mut args: aoc_utils::Args<I>) -> Result<Self> {
+        let mut options = Self {
+            models: vec![MovementModel::Aim],
+            plot: None,
+            script: None,
+            plan: None,
+            max_magnitude: None,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--model" => {
+                    let model = args.value()?;
+                    options.models = if "both".eq_ignore_ascii_case(&model) {
+                        vec![MovementModel::Naive, MovementModel::Aim]
+                    } else {
+                        vec![MovementModel::from_str(&model)?]
+                    };
+                }
+                "--plot" => options.plot = Some(args.parse()?),
+                "--script" => options.script = Some(args.value()?),
+                "--plan" => options.plan = Some((args.parse()?, args.parse()?)),
+                "--max-magnitude" => options.max_magnitude = Some(args.parse()?),
+                _ => return Err(args.unexpected()),
+            }
+        }
+
+        Ok(options)
+    }
+}
 
-    let mut pos = Position::default();
+fn main() -> Result<()> {
+    let options = Options::parse(aoc_utils::args())?;
 
-    for line in BufReader::new(File::open(&path)?).lines() {
-        pos.apply(line?.parse()?);
+    if let Some((x, depth)) = options.plan {
+        for action in planner::plan(x, depth, options.max_magnitude)? {
+            println!("{}", action);
+        }
+        return Ok(());
     }
 
-    println!("{}", pos.x * pos.y);
+    let courses = if let Some(path) = &options.script {
+        let script =
+            Script::parse(&fs::read_to_string(path)?).map_err(|e| anyhow!("{}, {}", path, e))?;
+
+        let mut courses = Vec::new();
+        for model in options.models.iter() {
+            let actions = script
+                .expand(*model)
+                .map_err(|e| anyhow!("{}, {}", path, e))?;
+            courses.push(Course::plot(*model, &actions));
+        }
+        courses
+    } else {
+        let path = Path::new(PATH);
+
+        let mut actions = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            actions.push(line?.parse()?);
+        }
+
+        options
+            .models
+            .iter()
+            .map(|m| Course::plot(*m, &actions))
+            .collect::<Vec<_>>()
+    };
+
+    match options.plot {
+        Some(PlotFormat::Csv) => print!("{}", plot::csv(&courses)),
+        Some(PlotFormat::Ascii) => print!("{}", plot::ascii(&courses, 80, 20)),
+        Some(PlotFormat::Svg) => print!("{}", plot::svg(&courses, 800, 400)),
+        None => {
+            for course in courses.iter() {
+                let pos = course.position();
+                println!("{}", pos.x * pos.y);
+            }
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aoc_utils::Args;
+
+    pub(crate) fn example() -> Vec<Action> {
+        vec![
+            "forward 5",
+            "down 5",
+            "forward 8",
+            "up 3",
+            "down 8",
+            "forward 2",
+        ]
+        .into_iter()
+        .map(|s| Action::from_str(s).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn test_models() {
+        let naive = Course::plot(MovementModel::Naive, &example());
+        assert_eq!(
+            Position {
+                x: 15,
+                y: 10,
+                aim: 0
+            },
+            naive.position()
+        );
+
+        let aim = Course::plot(MovementModel::Aim, &example());
+        assert_eq!(
+            Position {
+                x: 15,
+                y: 60,
+                aim: 10
+            },
+            aim.position()
+        );
+
+        assert_eq!(7, aim.track.len());
+        assert_eq!(
+            Position {
+                x: 13,
+                y: 40,
+                aim: 5
+            },
+            aim.track[3]
+        );
+    }
+
+    #[test]
+    fn test_format() -> Result<()> {
+        for s in ["forward 5", "down 3", "up 8"] {
+            assert_eq!(s, Action::from_str(s)?.to_string());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_options() -> Result<()> {
+        let options = Options::parse(Args::from_line("--model both --plot svg"))?;
+        assert_eq!(
+            vec![MovementModel::Naive, MovementModel::Aim],
+            options.models
+        );
+        assert_eq!(Some(PlotFormat::Svg), options.plot);
+
+        assert!(Options::parse(Args::from_line("--model sideways")).is_err());
+        assert!(Options::parse(Args::from_line("--plot")).is_err());
+
+        let options = Options::parse(Args::from_line("--plan 15 60 --max-magnitude 4"))?;
+        assert_eq!(Some((15, 60)), options.plan);
+        assert_eq!(Some(4), options.max_magnitude);
+        assert!(Options::parse(Args::from_line("--plan 15")).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,477 @@
+//! Synthesizes the shortest course reaching a target position under the aim model.
+//!
+//! Depth only changes while moving forward, by the current aim, so a plan is a series of
+//! segments each moving forward some distance at a fixed aim. Without a magnitude bound the
+//! shortest plan is one of:
+//!
+//! * `forward x` when the depth is zero
+//! * `down d/x, forward x` when `x` divides the depth
+//! * `forward x-f, down d/f, forward f` for some divisor `f < x` of the depth, which always
+//!   exists once `x > 1` since `f = 1` works
+//!
+//! With a bound, large divisors or aims take many commands, so the cheapest of those shapes and
+//! of plans splitting `x` such that the aims differ by one (`d = q*x + r`: `down q, forward x-r,
+//! down 1, forward r`) is only a starting point. An iterative deepening search over
+//! `(x, depth, aim)` then looks for anything shorter, pruned by a lower bound on the commands
+//! still needed.
+
+use super::{Action, Position};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// A stretch of the course moving `len` forward at a fixed `aim`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    aim: i64,
+    len: i64,
+}
+
+/// Splits `amt` into as few chunks as possible, none larger than `max` in magnitude.
+fn chunks(amt: i64, max: Option<i64>) -> Vec<i64> {
+    match max {
+        Some(max) if amt.abs() > max => {
+            let mut chunks = vec![max * amt.signum(); (amt.abs() / max) as usize];
+            if amt.abs() % max != 0 {
+                chunks.push(amt % max);
+            }
+            chunks
+        }
+        _ if amt == 0 => vec![],
+        _ => vec![amt],
+    }
+}
+
+/// Number of chunks `chunks` splits `amt` into.
+fn chunk_ct(amt: i64, max: Option<i64>) -> usize {
+    match max {
+        Some(max) => ((amt.abs() + max - 1) / max) as usize,
+        None => usize::from(amt != 0),
+    }
+}
+
+fn cost(segments: &[Segment], max: Option<i64>) -> usize {
+    let mut aim = 0;
+    let mut cost = 0;
+    for segment in segments {
+        cost += chunk_ct(segment.aim - aim, max) + chunk_ct(segment.len, max);
+        aim = segment.aim;
+    }
+    cost
+}
+
+fn to_actions(segments: &[Segment], max: Option<i64>) -> Result<Vec<Action>> {
+    let amt = |a: i64| i32::try_from(a).map_err(|_| anyhow!("Command out of range: {}", a));
+
+    // Commands are written as a positive distance, so even unbounded ones can't go past
+    // i32::MAX: an aim of i32::MIN becomes two commands, as `up 2147483648` can't be read back
+    let max = Some(max.unwrap_or(i32::MAX as i64));
+
+    let mut actions = Vec::new();
+    let mut aim = 0;
+    for segment in segments {
+        for chunk in chunks(segment.aim - aim, max) {
+            actions.push(Action::Vertical(amt(chunk)?));
+        }
+        for chunk in chunks(segment.len, max) {
+            actions.push(Action::Horizontal(amt(chunk)?));
+        }
+        aim = segment.aim;
+    }
+
+    Ok(actions)
+}
+
+fn candidates(x: i64, depth: i64) -> Vec<Vec<Segment>> {
+    let mut candidates = Vec::new();
+
+    if depth % x == 0 {
+        candidates.push(vec![Segment {
+            aim: depth / x,
+            len: x,
+        }]);
+    }
+
+    // Move forward without aiming first, then cover the depth with a divisor of it
+    let mut f = 1;
+    while f * f <= depth.abs() {
+        if depth % f == 0 {
+            for f in [f, depth.abs() / f] {
+                if f < x {
+                    candidates.push(vec![
+                        Segment { aim: 0, len: x - f },
+                        Segment {
+                            aim: depth / f,
+                            len: f,
+                        },
+                    ]);
+                }
+            }
+        }
+        f += 1;
+    }
+
+    // Split x so that the aims differ by one
+    let (q, r) = (depth.div_euclid(x), depth.rem_euclid(x));
+    if r != 0 {
+        candidates.push(vec![
+            Segment { aim: q, len: x - r },
+            Segment { aim: q + 1, len: r },
+        ]);
+        candidates.push(vec![
+            Segment { aim: q + 1, len: r },
+            Segment { aim: q, len: x - r },
+        ]);
+    }
+
+    candidates
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct State {
+    x: i64,
+    depth: i64,
+    aim: i64,
+}
+
+/// Searches for plans to (`x`, `depth`) of at most `limit` commands, none larger than `max`.
+struct Search {
+    x: i64,
+    depth: i64,
+    max: i64,
+    limit: usize,
+    /// Fewest commands each state has been reached in during this search.
+    seen: HashMap<State, usize>,
+    actions: Vec<Action>,
+}
+
+impl Search {
+    /// A lower bound on the commands needed to reach the target from `state`, or `None` if it
+    /// can't be reached. Every remaining step forward takes a command, and each command changing
+    /// the aim changes the final depth by at most `max` for each step forward still to come.
+    fn lower_bound(&self, state: State) -> Option<usize> {
+        let remaining = self.x - state.x;
+        if remaining == 0 {
+            return (state.depth == self.depth).then_some(0);
+        }
+
+        // How far off the target depth keeping the current aim ends up
+        let gap = self.depth - state.depth - state.aim * remaining;
+        let per_command = self.max * remaining;
+
+        Some(
+            chunk_ct(remaining, Some(self.max))
+                + ((gap.abs() + per_command - 1) / per_command) as usize,
+        )
+    }
+
+    fn visit(&mut self, state: State) -> bool {
+        let Some(bound) = self.lower_bound(state) else {
+            return false;
+        };
+
+        let taken = self.actions.len();
+        if bound == 0 {
+            return true;
+        }
+        if taken + bound > self.limit || self.seen.get(&state).is_some_and(|s| *s <= taken) {
+            return false;
+        }
+        self.seen.insert(state, taken);
+
+        let remaining = self.x - state.x;
+        for amt in (1..=self.max.min(remaining)).rev() {
+            let next = State {
+                x: state.x + amt,
+                depth: state.depth + state.aim * amt,
+                ..state
+            };
+            if self.step(Action::Horizontal(amt as i32), next) {
+                return true;
+            }
+        }
+
+        // Aim towards the target depth first
+        let towards = if self.depth - state.depth - state.aim * remaining < 0 {
+            -1
+        } else {
+            1
+        };
+        for amt in (1..=self.max).rev() {
+            for amt in [amt * towards, -amt * towards] {
+                let next = State {
+                    aim: state.aim + amt,
+                    ..state
+                };
+                if self.step(Action::Vertical(amt as i32), next) {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    fn step(&mut self, action: Action, next: State) -> bool {
+        self.actions.push(action);
+        if self.visit(next) {
+            return true;
+        }
+        self.actions.pop();
+        false
+    }
+}
+
+/// The shortest plan to (`x`, `depth`) with no command larger than `max`, if there's one with
+/// fewer than `limit` commands.
+fn search(x: i64, depth: i64, max: i64, limit: usize) -> Option<Vec<Action>> {
+    let mut search = Search {
+        x,
+        depth,
+        max,
+        limit: 0,
+        seen: HashMap::new(),
+        actions: Vec::new(),
+    };
+
+    let start = State {
+        x: 0,
+        depth: 0,
+        aim: 0,
+    };
+    for limit in search.lower_bound(start)?..limit {
+        search.limit = limit;
+        search.seen.clear();
+        if search.visit(start) {
+            return Some(search.actions);
+        }
+    }
+
+    None
+}
+
+/// Plans the shortest course from the origin to (`x`, `depth`), with no command larger than
+/// `max_magnitude`.
+pub fn plan(x: i32, depth: i32, max_magnitude: Option<i32>) -> Result<Vec<Action>> {
+    if x < 0 {
+        return Err(anyhow!("Can't reach negative x: {}", x));
+    }
+    if x == 0 && depth != 0 {
+        return Err(anyhow!("Can't change depth without moving forward"));
+    }
+    if max_magnitude.is_some_and(|m| m < 1) {
+        return Err(anyhow!("Max magnitude must be positive"));
+    }
+
+    let max = max_magnitude.map(i64::from);
+    let actions = if x == 0 {
+        vec![]
+    } else {
+        let best = candidates(x as i64, depth as i64)
+            .into_iter()
+            .min_by_key(|c| cost(c, max))
+            .unwrap();
+
+        match max.and_then(|max| search(x as i64, depth as i64, max, cost(&best, Some(max)))) {
+            Some(shorter) => shorter,
+            None => to_actions(&best, max)?,
+        }
+    };
+
+    verify(&actions, x, depth)?;
+
+    Ok(actions)
+}
+
+/// Replays `actions` under the aim model, checking they reach (`x`, `depth`).
+pub fn verify(actions: &[Action], x: i32, depth: i32) -> Result<()> {
+    let mut position = Position::default();
+    for action in actions {
+        position.apply(*action);
+    }
+
+    if (position.x, position.y) == (x, depth) {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Plan reaches ({}, {}) instead of ({}, {})",
+            position.x,
+            position.y,
+            x,
+            depth
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn plan_len(x: i32, depth: i32, max: Option<i32>) -> usize {
+        plan(x, depth, max).unwrap().len()
+    }
+
+    #[test]
+    fn test_unbounded() {
+        assert!(plan(0, 0, None).unwrap().is_empty());
+        assert_eq!(vec![Action::Horizontal(7)], plan(7, 0, None).unwrap());
+        assert_eq!(
+            vec![Action::Vertical(-3), Action::Horizontal(5)],
+            plan(5, -15, None).unwrap()
+        );
+
+        // 10 doesn't divide 14, but 7 does
+        assert_eq!(3, plan_len(10, 14, None));
+
+        // 13 is prime, so only a single step forward can cover it
+        assert_eq!(
+            vec![
+                Action::Horizontal(4),
+                Action::Vertical(-13),
+                Action::Horizontal(1)
+            ],
+            plan(5, -13, None).unwrap()
+        );
+        assert_eq!(2, plan_len(1, 13, None));
+
+        // Every command is written out and read back the same, even at the edges of i32
+        for (x, depth) in [
+            (1, i32::MIN),
+            (3, i32::MIN),
+            (1, i32::MAX),
+            (i32::MAX, i32::MIN),
+        ] {
+            for action in plan(x, depth, None).unwrap() {
+                assert_eq!(action, Action::from_str(&action.to_string()).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_bounded() {
+        // Aiming 13 down would take 7 commands, while aims of 2 and 3 are cheap
+        assert_eq!(
+            vec![
+                Action::Vertical(2),
+                Action::Horizontal(2),
+                Action::Vertical(1),
+                Action::Horizontal(2),
+                Action::Horizontal(1)
+            ],
+            plan(5, 13, Some(2)).unwrap()
+        );
+
+        assert_eq!(
+            vec![
+                Action::Horizontal(3),
+                Action::Horizontal(3),
+                Action::Horizontal(1)
+            ],
+            plan(7, 0, Some(3)).unwrap()
+        );
+
+        for (x, depth, max) in [(15, 60, 4), (100, -977, 10), (3, 1000, 7), (50, 1, 1)] {
+            let actions = plan(x, depth, Some(max)).unwrap();
+            assert!(actions.iter().all(|a| match a {
+                Action::Horizontal(amt) | Action::Vertical(amt) => amt.abs() <= max,
+            }));
+            verify(&actions, x, depth).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_exhaustive() {
+        for x in 1..20 {
+            for depth in -50..50 {
+                for max in [None, Some(1), Some(4)] {
+                    verify(&plan(x, depth, max).unwrap(), x, depth).unwrap();
+                }
+            }
+        }
+    }
+
+    /// Fewest commands reaching each position with `x <= max_x`, found by a breadth-first search
+    /// over every command. Aims and depths are bounded well past anything a short plan to a
+    /// nearby target could use.
+    fn brute_force(max_x: i64, max: i64) -> HashMap<(i64, i64), usize> {
+        let start = State {
+            x: 0,
+            depth: 0,
+            aim: 0,
+        };
+
+        let mut seen = HashMap::from([(start, 0)]);
+        let mut queue = std::collections::VecDeque::from([start]);
+        while let Some(state) = queue.pop_front() {
+            let ct = seen[&state];
+
+            let forwards = (1..=max).map(|amt| State {
+                x: state.x + amt,
+                depth: state.depth + state.aim * amt,
+                ..state
+            });
+            let aims = (-max..=max).filter(|amt| *amt != 0).map(|amt| State {
+                aim: state.aim + amt,
+                ..state
+            });
+
+            for next in forwards.chain(aims) {
+                if next.x <= max_x
+                    && next.aim.abs() <= 25
+                    && next.depth.abs() <= 200
+                    && !seen.contains_key(&next)
+                {
+                    seen.insert(next, ct + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut shortest = HashMap::new();
+        for (state, ct) in seen {
+            let best = shortest.entry((state.x, state.depth)).or_insert(ct);
+            *best = (*best).min(ct);
+        }
+        shortest
+    }
+
+    #[test]
+    fn test_minimal() {
+        // Shapes with a single change of aim can't do these in as few commands
+        assert_eq!(
+            vec![
+                Action::Vertical(3),
+                Action::Horizontal(1),
+                Action::Vertical(3),
+                Action::Horizontal(1)
+            ],
+            plan(2, 9, Some(3)).unwrap()
+        );
+        assert_eq!(4, plan_len(3, 10, Some(2)));
+        assert_eq!(5, plan_len(5, -18, Some(2)));
+
+        for max in 1..=3 {
+            let shortest = brute_force(8, max);
+            for x in 1..=8 {
+                for depth in -20..=20 {
+                    assert_eq!(
+                        shortest[&(x, depth)],
+                        plan_len(x as i32, depth as i32, Some(max as i32)),
+                        "({}, {}) with max {}",
+                        x,
+                        depth,
+                        max
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unreachable() {
+        assert!(plan(-1, 0, None).is_err());
+        assert!(plan(0, 5, None).is_err());
+        assert!(plan(5, 5, Some(0)).is_err());
+        assert!(verify(&[Action::Horizontal(1)], 2, 0).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+use super::{Course, MovementModel};
+use std::fmt::Write;
+
+const SVG_MARGIN: i64 = 40;
+
+/// Bounds of every position across all courses, as (min_x, max_x, min_depth, max_depth).
+fn bounds(courses: &[Course]) -> (i64, i64, i64, i64) {
+    let positions = || courses.iter().flat_map(|c| c.track.iter());
+
+    (
+        positions().map(|p| p.x as i64).min().unwrap_or(0),
+        positions().map(|p| p.x as i64).max().unwrap_or(0),
+        positions().map(|p| p.y as i64).min().unwrap_or(0),
+        positions().map(|p| p.y as i64).max().unwrap_or(0),
+    )
+}
+
+/// Maps `value` in `min..=max` onto `0..=len`.
+fn scale(value: i64, min: i64, max: i64, len: i64) -> i64 {
+    if max == min {
+        0
+    } else {
+        (value - min) * len / (max - min)
+    }
+}
+
+fn symbol(model: MovementModel) -> char {
+    match model {
+        MovementModel::Naive => 'n',
+        MovementModel::Aim => 'a',
+    }
+}
+
+fn color(model: MovementModel) -> &'static str {
+    match model {
+        MovementModel::Naive => "steelblue",
+        MovementModel::Aim => "darkorange",
+    }
+}
+
+pub fn csv(courses: &[Course]) -> String {
+    let mut out = String::from("model,step,x,depth,aim\n");
+
+    for course in courses {
+        for (step, p) in course.track.iter().enumerate() {
+            writeln!(out, "{},{},{},{},{}", course.model, step, p.x, p.y, p.aim).unwrap();
+        }
+    }
+
+    out
+}
+
+/// Depth profile with depth increasing downwards. Positions shared by different courses are
+/// marked with `*`.
+pub fn ascii(courses: &[Course], width: usize, height: usize) -> String {
+    let (min_x, max_x, min_y, max_y) = bounds(courses);
+
+    let mut grid = vec![vec![' '; width]; height];
+    for course in courses {
+        let symbol = symbol(course.model);
+
+        for p in course.track.iter() {
+            let col = scale(p.x as i64, min_x, max_x, width as i64 - 1) as usize;
+            let row = scale(p.y as i64, min_y, max_y, height as i64 - 1) as usize;
+
+            let cell = &mut grid[row][col];
+            *cell = if *cell == ' ' || *cell == symbol {
+                symbol
+            } else {
+                '*'
+            };
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(out, "x: {}..{}, depth: {}..{}", min_x, max_x, min_y, max_y).unwrap();
+    writeln!(
+        out,
+        "{}",
+        courses
+            .iter()
+            .map(|c| format!("{} = {}", symbol(c.model), c.model))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+    .unwrap();
+
+    for row in grid {
+        writeln!(out, "|{}", row.into_iter().collect::<String>().trim_end()).unwrap();
+    }
+
+    out
+}
+
+pub fn svg(courses: &[Course], width: usize, height: usize) -> String {
+    let (min_x, max_x, min_y, max_y) = bounds(courses);
+    let (width, height) = (width as i64, height as i64);
+    let (plot_width, plot_height) = (width - 2 * SVG_MARGIN, height - 2 * SVG_MARGIN);
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        width, height, width, height
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r#"<rect width="{}" height="{}" fill="white"/>"#,
+        width, height
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="gray"/>"#,
+        SVG_MARGIN, SVG_MARGIN, plot_width, plot_height
+    )
+    .unwrap();
+
+    for course in courses {
+        let points = course
+            .track
+            .iter()
+            .map(|p| {
+                format!(
+                    "{},{}",
+                    SVG_MARGIN + scale(p.x as i64, min_x, max_x, plot_width),
+                    SVG_MARGIN + scale(p.y as i64, min_y, max_y, plot_height)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        writeln!(
+            out,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+            points,
+            color(course.model)
+        )
+        .unwrap();
+    }
+
+    // Axis ranges
+    writeln!(
+        out,
+        r#"<text x="{}" y="{}" font-size="12">x {}..{}</text>"#,
+        SVG_MARGIN,
+        height - SVG_MARGIN / 3,
+        min_x,
+        max_x
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r#"<text x="{}" y="{}" font-size="12">depth {}..{}</text>"#,
+        SVG_MARGIN,
+        SVG_MARGIN * 2 / 3,
+        min_y,
+        max_y
+    )
+    .unwrap();
+
+    // Legend
+    for (i, course) in courses.iter().enumerate() {
+        let y = SVG_MARGIN * 2 / 3 + 16 * i as i64;
+        writeln!(
+            out,
+            r#"<text x="{}" y="{}" font-size="12" fill="{}">{}</text>"#,
+            width - SVG_MARGIN - 60,
+            y,
+            color(course.model),
+            course.model
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "</svg>").unwrap();
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::example;
+
+    fn courses() -> Vec<Course> {
+        vec![
+            Course::plot(MovementModel::Naive, &example()),
+            Course::plot(MovementModel::Aim, &example()),
+        ]
+    }
+
+    #[test]
+    fn test_csv() {
+        let csv = csv(&courses());
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!(15, lines.len());
+        assert_eq!("model,step,x,depth,aim", lines[0]);
+        assert_eq!("naive,6,15,10,0", lines[7]);
+        assert_eq!("aim,6,15,60,10", lines[14]);
+    }
+
+    #[test]
+    fn test_ascii() {
+        let ascii = ascii(&courses(), 16, 7);
+
+        assert_eq!(
+            "x: 0..15, depth: 0..60\n\
+             n = naive, a = aim\n\
+             |*    *       n\n\
+             |             n n\n\
+             |\n\
+             |\n\
+             |             a\n\
+             |\n\
+             |               a\n",
+            ascii
+        );
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = svg(&courses(), 800, 400);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(2, svg.matches("<polyline").count());
+        assert!(svg.contains(r#"points="40,40 280,40 280,66 "#));
+    }
+}
@@ -0,0 +1,618 @@
+//! A small command language which expands into primitive actions:
+//!
+//! ```text
+//! # Comments run to the end of the line
+//! macro dive { down 5 forward 2 }
+//! repeat 3 { dive; up 1 }
+//! goto depth 40
+//! forward 10
+//! ```
+//!
+//! Statements may be separated by whitespace, newlines or `;`. Macros must be defined at the top
+//! level before they're used.
+
+use super::{Action, MovementModel, Position};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+
+/// Guards against `repeat` blocks which would expand into an unreasonable number of actions.
+const MAX_ACTIONS: usize = 10_000_000;
+/// Guards against `repeat` blocks which run for an unreasonably long time without producing any
+/// actions, e.g. repeating an empty block. Iterations which do produce actions are already bounded
+/// by `MAX_ACTIONS`.
+const MAX_EMPTY_ITERATIONS: usize = 1_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    col: usize,
+    message: String,
+}
+
+impl ParseError {
+    fn new<S: Into<String>>(pos: Pos, message: S) -> Self {
+        Self {
+            line: pos.line,
+            col: pos.col,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.col, self.message
+        )
+    }
+}
+
+impl error::Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pos {
+    line: usize,
+    col: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Int(i32),
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Word(w) => write!(f, "'{}'", w),
+            Self::Int(i) => write!(f, "'{}'", i),
+            Self::Open => write!(f, "'{{'"),
+            Self::Close => write!(f, "'}}'"),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Pos, Token)>, ParseError> {
+    let mut tokens = Vec::new();
+
+    for (line_idx, line) in source.lines().enumerate() {
+        let chars = line.chars().collect::<Vec<_>>();
+
+        let mut i = 0;
+        while i < chars.len() {
+            let pos = Pos {
+                line: line_idx + 1,
+                col: i + 1,
+            };
+            let c = chars[i];
+
+            if c == '#' {
+                break;
+            } else if c.is_whitespace() || c == ';' {
+                i += 1;
+            } else if c == '{' {
+                tokens.push((pos, Token::Open));
+                i += 1;
+            } else if c == '}' {
+                tokens.push((pos, Token::Close));
+                i += 1;
+            } else if c.is_ascii_digit() || c == '-' {
+                let start = i;
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+
+                let s = chars[start..i].iter().collect::<String>();
+                let value = s
+                    .parse()
+                    .map_err(|e| ParseError::new(pos, format!("Invalid number '{}': {}", s, e)))?;
+                tokens.push((pos, Token::Int(value)));
+            } else if c.is_alphabetic() || c == '_' {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+
+                tokens.push((pos, Token::Word(chars[start..i].iter().collect())));
+            } else {
+                return Err(ParseError::new(
+                    pos,
+                    format!("Unexpected character '{}'", c),
+                ));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Depth,
+    X,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Stmt {
+    Action(Pos, Action),
+    Repeat(Pos, u32, Vec<Stmt>),
+    Goto(Pos, Target, i32),
+    Call(String),
+}
+
+const KEYWORDS: [&str; 6] = ["forward", "down", "up", "repeat", "macro", "goto"];
+
+struct Parser {
+    tokens: Vec<(Pos, Token)>,
+    next: usize,
+    end: Pos,
+    macros: HashMap<String, Vec<Stmt>>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&(Pos, Token)> {
+        self.tokens.get(self.next)
+    }
+
+    fn advance(&mut self, expected: &str) -> Result<(Pos, Token), ParseError> {
+        let token = self.tokens.get(self.next).cloned().ok_or_else(|| {
+            ParseError::new(
+                self.end,
+                format!("Expected {}, found end of input", expected),
+            )
+        })?;
+        self.next += 1;
+        Ok(token)
+    }
+
+    fn int(&mut self, expected: &str) -> Result<(Pos, i32), ParseError> {
+        match self.advance(expected)? {
+            (pos, Token::Int(i)) => Ok((pos, i)),
+            (pos, t) => Err(ParseError::new(
+                pos,
+                format!("Expected {}, found {}", expected, t),
+            )),
+        }
+    }
+
+    fn word(&mut self, expected: &str) -> Result<(Pos, String), ParseError> {
+        match self.advance(expected)? {
+            (pos, Token::Word(w)) => Ok((pos, w)),
+            (pos, t) => Err(ParseError::new(
+                pos,
+                format!("Expected {}, found {}", expected, t),
+            )),
+        }
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        match self.advance("'{'")? {
+            (_, Token::Open) => (),
+            (pos, t) => return Err(ParseError::new(pos, format!("Expected '{{', found {}", t))),
+        }
+
+        let mut stmts = Vec::new();
+        loop {
+            match self.peek() {
+                Some((_, Token::Close)) => {
+                    self.next += 1;
+                    return Ok(stmts);
+                }
+                Some(_) => stmts.extend(self.stmt(false)?),
+                None => return Err(ParseError::new(self.end, "Unclosed '{'")),
+            }
+        }
+    }
+
+    /// Parses the next statement; macro definitions produce no statement of their own.
+    fn stmt(&mut self, top_level: bool) -> Result<Option<Stmt>, ParseError> {
+        let (pos, word) = self.word("a command")?;
+
+        let stmt = match word.as_str() {
+            "forward" => Stmt::Action(pos, Action::Horizontal(self.int("a distance")?.1)),
+            "down" => Stmt::Action(pos, Action::Vertical(self.int("a distance")?.1)),
+            "up" => {
+                let (distance_pos, distance) = self.int("a distance")?;
+                let distance = distance.checked_neg().ok_or_else(|| {
+                    ParseError::new(distance_pos, format!("Distance out of range: {}", distance))
+                })?;
+                Stmt::Action(pos, Action::Vertical(distance))
+            }
+            "repeat" => {
+                let (ct_pos, ct) = self.int("a repeat count")?;
+                let ct = u32::try_from(ct).map_err(|_| {
+                    ParseError::new(ct_pos, format!("Invalid repeat count: {}", ct))
+                })?;
+                Stmt::Repeat(pos, ct, self.block()?)
+            }
+            "goto" => {
+                let (target_pos, target) = self.word("'depth' or 'x'")?;
+                let target = match target.as_str() {
+                    "depth" => Target::Depth,
+                    "x" => Target::X,
+                    _ => {
+                        return Err(ParseError::new(
+                            target_pos,
+                            format!("Expected 'depth' or 'x', found '{}'", target),
+                        ))
+                    }
+                };
+                Stmt::Goto(pos, target, self.int("a target")?.1)
+            }
+            "macro" => {
+                if !top_level {
+                    return Err(ParseError::new(
+                        pos,
+                        "Macros must be defined at the top level",
+                    ));
+                }
+
+                let (name_pos, name) = self.word("a macro name")?;
+                if KEYWORDS.contains(&name.as_str()) {
+                    return Err(ParseError::new(
+                        name_pos,
+                        format!("'{}' is a reserved word", name),
+                    ));
+                }
+                if self.macros.contains_key(&name) {
+                    return Err(ParseError::new(
+                        name_pos,
+                        format!("Macro '{}' is already defined", name),
+                    ));
+                }
+
+                let body = self.block()?;
+                self.macros.insert(name, body);
+                return Ok(None);
+            }
+            _ if self.macros.contains_key(&word) => Stmt::Call(word),
+            _ => return Err(ParseError::new(pos, format!("Unknown command '{}'", word))),
+        };
+
+        Ok(Some(stmt))
+    }
+}
+
+#[derive(Debug)]
+pub struct Script {
+    stmts: Vec<Stmt>,
+    macros: HashMap<String, Vec<Stmt>>,
+}
+
+impl Script {
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let end = Pos {
+            line: source.lines().count().max(1),
+            col: source.lines().last().map_or(0, |l| l.chars().count()) + 1,
+        };
+
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            next: 0,
+            end,
+            macros: HashMap::new(),
+        };
+
+        let mut stmts = Vec::new();
+        while parser.peek().is_some() {
+            stmts.extend(parser.stmt(true)?);
+        }
+
+        Ok(Self {
+            stmts,
+            macros: parser.macros,
+        })
+    }
+
+    /// Expands the script into primitive actions. Targeted commands depend on where the
+    /// submarine is at that point, so they're resolved by following the course under `model`.
+    pub fn expand(&self, model: MovementModel) -> Result<Vec<Action>, ParseError> {
+        let mut expansion = Expansion {
+            model,
+            position: Position::default(),
+            actions: Vec::new(),
+            empty_iterations: 0,
+        };
+
+        expansion.stmts(&self.stmts, &self.macros)?;
+
+        Ok(expansion.actions)
+    }
+}
+
+struct Expansion {
+    model: MovementModel,
+    position: Position,
+    actions: Vec<Action>,
+    /// Iterations of every `repeat` so far which produced no actions.
+    empty_iterations: usize,
+}
+
+fn out_of_range(pos: Pos) -> ParseError {
+    ParseError::new(pos, "Moves out of range")
+}
+
+impl Expansion {
+    fn push(&mut self, pos: Pos, action: Action) -> Result<(), ParseError> {
+        self.position = self
+            .model
+            .checked_apply(&self.position, action)
+            .ok_or_else(|| out_of_range(pos))?;
+        self.actions.push(action);
+        Ok(())
+    }
+
+    fn stmts(
+        &mut self,
+        stmts: &[Stmt],
+        macros: &HashMap<String, Vec<Stmt>>,
+    ) -> Result<(), ParseError> {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Action(pos, action) => self.push(*pos, *action)?,
+                Stmt::Repeat(pos, ct, body) => {
+                    for _ in 0..*ct {
+                        let action_ct = self.actions.len();
+                        self.stmts(body, macros)?;
+
+                        if self.actions.len() == action_ct {
+                            self.empty_iterations += 1;
+                            if self.empty_iterations > MAX_EMPTY_ITERATIONS {
+                                return Err(ParseError::new(
+                                    *pos,
+                                    format!(
+                                        "Repeats more than {} times without any actions",
+                                        MAX_EMPTY_ITERATIONS
+                                    ),
+                                ));
+                            }
+                        }
+
+                        if self.actions.len() > MAX_ACTIONS {
+                            return Err(ParseError::new(
+                                *pos,
+                                format!("Expands to more than {} actions", MAX_ACTIONS),
+                            ));
+                        }
+                    }
+                }
+                Stmt::Call(name) => self.stmts(&macros[name], macros)?,
+                Stmt::Goto(pos, target, value) => self.goto(*pos, *target, *value)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn goto(&mut self, pos: Pos, target: Target, value: i32) -> Result<(), ParseError> {
+        match target {
+            Target::X => {
+                let delta = value
+                    .checked_sub(self.position.x)
+                    .ok_or_else(|| out_of_range(pos))?;
+                if delta < 0 {
+                    return Err(ParseError::new(
+                        pos,
+                        format!("Can't move back to x={} from x={}", value, self.position.x),
+                    ));
+                }
+                if delta > 0 {
+                    self.push(pos, Action::Horizontal(delta))?;
+                }
+            }
+            Target::Depth => {
+                let delta = value
+                    .checked_sub(self.position.y)
+                    .ok_or_else(|| out_of_range(pos))?;
+                if delta == 0 {
+                    return Ok(());
+                }
+
+                match self.model {
+                    MovementModel::Naive => self.push(pos, Action::Vertical(delta))?,
+                    MovementModel::Aim => {
+                        // Aim at the target so that a single step forward reaches it, then
+                        // restore the previous aim.
+                        let aim = self.position.aim;
+                        let turn = delta.checked_sub(aim).ok_or_else(|| out_of_range(pos))?;
+                        let restore = turn.checked_neg().ok_or_else(|| out_of_range(pos))?;
+                        if turn != 0 {
+                            self.push(pos, Action::Vertical(turn))?;
+                        }
+                        self.push(pos, Action::Horizontal(1))?;
+                        if turn != 0 {
+                            self.push(pos, Action::Vertical(restore))?;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn expand(source: &str, model: MovementModel) -> Vec<Action> {
+        Script::parse(source).unwrap().expand(model).unwrap()
+    }
+
+    fn parse_err(source: &str) -> String {
+        Script::parse(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_repeat_and_macros() {
+        let source = "\
+# Dive in steps
+macro dive {
+    down 5; forward 2 # inline comment
+}
+
+repeat 2 { dive up 1 }
+forward 3";
+
+        assert_eq!(
+            vec![
+                Action::Vertical(5),
+                Action::Horizontal(2),
+                Action::Vertical(-1),
+                Action::Vertical(5),
+                Action::Horizontal(2),
+                Action::Vertical(-1),
+                Action::Horizontal(3),
+            ],
+            expand(source, MovementModel::Aim)
+        );
+
+        assert!(expand("repeat 0 { forward 1 }", MovementModel::Aim).is_empty());
+        assert_eq!(
+            16,
+            expand(
+                "repeat 4 { repeat 2 { up 1 forward 1 } }",
+                MovementModel::Naive
+            )
+            .len()
+        );
+    }
+
+    #[test]
+    fn test_goto() {
+        for model in [MovementModel::Naive, MovementModel::Aim] {
+            let actions = expand("down 3 forward 4 goto x 10 goto depth 20", model);
+
+            let mut position = Position::default();
+            for action in actions {
+                model.apply(&mut position, action);
+            }
+
+            assert_eq!(20, position.y, "{}", model);
+            match model {
+                MovementModel::Naive => assert_eq!((10, 0), (position.x, position.aim)),
+                // Reaching the depth takes one more step forward, and the aim is restored
+                MovementModel::Aim => assert_eq!((11, 3), (position.x, position.aim)),
+            }
+        }
+
+        let err = Script::parse("repeat 100000 {\n  repeat 1000 { up 1 }\n}")
+            .unwrap()
+            .expand(MovementModel::Naive)
+            .unwrap_err();
+        assert_eq!(
+            "line 2, column 3: Expands to more than 10000000 actions",
+            err.to_string()
+        );
+
+        let err = Script::parse("repeat 2000000000 {\n  repeat 2000000000 { }\n}")
+            .unwrap()
+            .expand(MovementModel::Naive)
+            .unwrap_err();
+        assert_eq!(
+            "line 2, column 3: Repeats more than 1000000 times without any actions",
+            err.to_string()
+        );
+
+        let err = Script::parse("macro nothing { }\nrepeat 2000000000 { nothing }")
+            .unwrap()
+            .expand(MovementModel::Aim)
+            .unwrap_err();
+        assert_eq!(
+            "line 2, column 1: Repeats more than 1000000 times without any actions",
+            err.to_string()
+        );
+
+        let err = Script::parse("forward 5\ngoto x 2")
+            .unwrap()
+            .expand(MovementModel::Naive)
+            .unwrap_err();
+        assert_eq!(
+            "line 2, column 1: Can't move back to x=2 from x=5",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            "line 2, column 9: Expected a distance, found 'up'",
+            parse_err("down 1\nforward up 2")
+        );
+        assert_eq!(
+            "line 1, column 1: Unknown command 'dive'",
+            parse_err("dive\nmacro dive { down 1 }")
+        );
+        assert_eq!("line 1, column 12: Unclosed '{'", parse_err("repeat 2 { "));
+        assert_eq!(
+            "line 1, column 8: Expected a distance, found end of input",
+            parse_err("forward")
+        );
+        assert_eq!(
+            "line 1, column 12: Macros must be defined at the top level",
+            parse_err("repeat 1 { macro m { up 1 } }")
+        );
+        assert_eq!(
+            "line 1, column 7: 'up' is a reserved word",
+            parse_err("macro up { down 1 }")
+        );
+        assert_eq!(
+            "line 1, column 6: Expected 'depth' or 'x', found 'aim'",
+            parse_err("goto aim 3")
+        );
+        assert_eq!(
+            "line 3, column 3: Unexpected character '!'",
+            parse_err("\n\n  !")
+        );
+        assert_eq!(
+            "line 1, column 8: Invalid repeat count: -1",
+            parse_err("repeat -1 { up 1 }")
+        );
+        assert_eq!(
+            "line 1, column 4: Distance out of range: -2147483648",
+            parse_err("up -2147483648")
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let expand_err = |source: &str, model: MovementModel| {
+            Script::parse(source)
+                .unwrap()
+                .expand(model)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            "line 2, column 1: Moves out of range",
+            expand_err("up 2000000000\ngoto depth 2000000000", MovementModel::Naive)
+        );
+        assert_eq!(
+            "line 2, column 1: Moves out of range",
+            expand_err(
+                "down 2000000000\ngoto depth -2000000000",
+                MovementModel::Aim
+            )
+        );
+        assert_eq!(
+            "line 2, column 1: Moves out of range",
+            expand_err(
+                "forward 2000000000\nforward 2000000000",
+                MovementModel::Naive
+            )
+        );
+        assert_eq!(
+            "line 2, column 1: Moves out of range",
+            expand_err("forward 2000000000\ngoto x -2000000000", MovementModel::Aim)
+        );
+    }
+}
@@ -1,119 +1,576 @@
-use anyhow::Result;
-use env_logger::Target;
+use anyhow::{anyhow, Result};
 use log::{debug, LevelFilter};
-use std::fs::File;
-use std::io::{prelude::*, BufReader};
-use std::path::Path;
+use num_bigint::BigUint;
+use std::str::FromStr;
 
 const PATH: &str = "input/day03.txt";
 
-fn get_ones_cts(lines: &[String]) -> Vec<u32> {
-    let mut ones_cts = Vec::new();
-    for line in lines.iter() {
-        for (i, c) in line.chars().enumerate() {
-            if ones_cts.len() < i + 1 {
-                ones_cts.push(0);
+/// The digits a report is written in, in increasing order of value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Alphabet {
+    symbols: Vec<char>,
+}
+
+impl Alphabet {
+    fn binary() -> Self {
+        Self {
+            symbols: vec!['0', '1'],
+        }
+    }
+
+    fn radix(&self) -> usize {
+        self.symbols.len()
+    }
+
+    fn digit(&self, symbol: char) -> Option<usize> {
+        self.symbols.iter().position(|s| *s == symbol)
+    }
+
+    fn format(&self, value: u128, width: usize) -> String {
+        let radix = self.radix() as u128;
+
+        let mut digits = Vec::with_capacity(width);
+        let mut value = value;
+        for _ in 0..width {
+            digits.push(self.symbols[(value % radix) as usize]);
+            value /= radix;
+        }
+
+        digits.into_iter().rev().collect()
+    }
+}
+
+impl FromStr for Alphabet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let symbols = s.chars().collect::<Vec<_>>();
+
+        if symbols.len() < 2 {
+            return Err(anyhow!("Alphabet needs at least two symbols: '{}'", s));
+        }
+
+        for (i, c) in symbols.iter().enumerate() {
+            if symbols[..i].contains(c) {
+                return Err(anyhow!("Duplicate symbol '{}' in alphabet '{}'", c, s));
             }
-            ones_cts[i] += if c == '1' { 1 } else { 0 };
         }
+
+        Ok(Self { symbols })
     }
-    ones_cts
 }
 
-fn get_ones_cts_and_lines() -> Result<(Vec<u32>, Vec<String>)> {
-    let lines: Vec<String> = BufReader::new(File::open(&Path::new(PATH))?)
-        .lines()
-        .map(|l| l.unwrap())
-        .collect();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Commonness {
+    Most,
+    Least,
+}
+
+/// Which digit to keep in each position: the most or least common, and which to prefer when
+/// several digits are equally common. If the tie-break isn't among them, the lowest is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Criteria {
+    commonness: Commonness,
+    tie_break: char,
+}
+
+impl Criteria {
+    const GAMMA: Self = Self {
+        commonness: Commonness::Most,
+        tie_break: '1',
+    };
+
+    const EPSILON: Self = Self {
+        commonness: Commonness::Least,
+        tie_break: '0',
+    };
+
+    const O2: Self = Self::GAMMA;
 
-    let ones_cts = get_ones_cts(&lines);
+    const CO2: Self = Self::EPSILON;
 
-    Ok((ones_cts, lines))
+    /// Picks from `counts`, indexed by digit, ignoring digits for which `candidate` is false.
+    fn select<F>(&self, alphabet: &Alphabet, counts: &[usize], candidate: F) -> usize
+    where
+        F: Fn(usize) -> bool,
+    {
+        let candidates = (0..counts.len()).filter(|d| candidate(*d));
+
+        let best = match self.commonness {
+            Commonness::Most => candidates.clone().map(|d| counts[d]).max(),
+            Commonness::Least => candidates.clone().map(|d| counts[d]).min(),
+        };
+
+        let mut tied = candidates.filter(|d| Some(counts[*d]) == best).peekable();
+        let first = *tied.peek().unwrap();
+
+        match alphabet.digit(self.tie_break) {
+            Some(tie_break) if tied.any(|d| d == tie_break) => tie_break,
+            _ => first,
+        }
+    }
 }
 
-fn _power_consumption() -> Result<u32> {
-    let (ones_ct, lines) = get_ones_cts_and_lines()?;
-    let line_ct = lines.len() as u32;
+impl FromStr for Criteria {
+    type Err = anyhow::Error;
 
-    let gamma: String = ones_ct
-        .iter()
-        .map(|i| if 2 * *i >= line_ct { '1' } else { '0' })
-        .collect();
-    let epsilon: String = gamma
-        .chars()
-        .map(|c| if c == '0' { '1' } else { '0' })
-        .collect();
+    /// Parses `most:<tie-break>` or `least:<tie-break>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (commonness, tie_break) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Expected <most|least>:<tie-break>: '{}'", s))?;
 
-    let gamma = u32::from_str_radix(&gamma, 2)?;
-    let epsilon = u32::from_str_radix(&epsilon, 2)?;
+        let commonness = match commonness {
+            "most" => Commonness::Most,
+            "least" => Commonness::Least,
+            _ => return Err(anyhow!("Expected most or least: '{}'", commonness)),
+        };
 
-    debug!("Gamma:  {:0b} ({})", gamma, gamma);
-    debug!("Epsilon {:0b} ({})", epsilon, epsilon);
+        let mut tie_break = tie_break.chars();
+        match (tie_break.next(), tie_break.next()) {
+            (Some(tie_break), None) => Ok(Self {
+                commonness,
+                tie_break,
+            }),
+            _ => Err(anyhow!("Tie-break must be a single symbol: '{}'", s)),
+        }
+    }
+}
 
-    Ok(gamma * epsilon)
+/// Trie over the readings, most significant digit first, where each node counts the readings
+/// below it.
+#[derive(Debug)]
+struct Trie {
+    counts: Vec<usize>,
+    /// Children of node `n` are at `n * radix..(n + 1) * radix`, indexed by digit, where 0 (the
+    /// root) means there's no child.
+    children: Vec<usize>,
+    radix: usize,
 }
 
-fn life_support() -> Result<u32> {
-    let (ones_cts, lines) = get_ones_cts_and_lines()?;
-    let ones_cts_len = ones_cts.len();
+impl Trie {
+    fn new(radix: usize) -> Self {
+        Self {
+            counts: vec![0],
+            children: vec![0; radix],
+            radix,
+        }
+    }
 
-    let mut o2_ones_cts = ones_cts;
-    let mut co2_ones_cts = o2_ones_cts.clone();
+    fn child(&self, node: usize, digit: usize) -> usize {
+        self.children[node * self.radix + digit]
+    }
 
-    let mut o2_lines = lines;
-    let mut co2_lines = o2_lines.clone();
+    fn insert<I: Iterator<Item = usize>>(&mut self, digits: I) {
+        let mut node = 0;
+        self.counts[node] += 1;
+
+        for digit in digits {
+            if self.child(node, digit) == 0 {
+                self.counts.push(0);
+                self.children.extend(std::iter::repeat_n(0, self.radix));
+                self.children[node * self.radix + digit] = self.counts.len() - 1;
+            }
 
-    for i in 0..ones_cts_len {
-        let o2_lines_ct = o2_lines.len() as u32;
-        if o2_lines.len() != 1 {
-            let more_ones = 2 * o2_ones_cts[i] >= o2_lines_ct;
-            let o2_filter = if more_ones { '1' } else { '0' };
+            node = self.child(node, digit);
+            self.counts[node] += 1;
+        }
+    }
+
+    fn child_counts(&self, node: usize) -> Vec<usize> {
+        (0..self.radix)
+            .map(|d| match self.child(node, d) {
+                0 => 0,
+                child => self.counts[child],
+            })
+            .collect()
+    }
 
-            o2_lines = o2_lines
-                .into_iter()
-                .filter(|l| l.chars().nth(i).unwrap() == o2_filter)
-                .collect();
+    /// Follows the child selected by `criteria` at each level, which is the same as repeatedly
+    /// filtering the readings by the digit in each position until one remains. Only digits which
+    /// some remaining reading has are considered, so a position where every remaining reading
+    /// agrees is kept as-is.
+    fn rating(&self, alphabet: &Alphabet, criteria: &Criteria, width: usize) -> u128 {
+        let mut node = 0;
+        let mut rating = 0;
 
-            o2_ones_cts = get_ones_cts(&o2_lines);
+        for _ in 0..width {
+            let counts = self.child_counts(node);
+            let digit = criteria.select(alphabet, &counts, |d| counts[d] > 0);
+
+            rating = rating * self.radix as u128 + digit as u128;
+            node = self.child(node, digit);
         }
 
-        if co2_lines.len() != 1 {
-            let co2_lines_ct = co2_lines.len() as u32;
-            let more_ones = 2 * co2_ones_cts[i] >= co2_lines_ct;
-            let co2_filter = if more_ones { '0' } else { '1' };
+        rating
+    }
+}
+
+/// Readings packed into integers, with their digits in the report's alphabet.
+#[derive(Debug)]
+struct DiagnosticReport {
+    alphabet: Alphabet,
+    readings: Vec<u128>,
+    width: usize,
+    /// Value of each position, from the left as the report is written.
+    place_values: Vec<u128>,
+    trie: Trie,
+}
 
-            co2_lines = co2_lines
-                .into_iter()
-                .filter(|l| l.chars().nth(i).unwrap() == co2_filter)
-                .collect();
+impl DiagnosticReport {
+    fn parse(lines: &[String], alphabet: Alphabet) -> Result<Self> {
+        let width = lines
+            .first()
+            .map(|l| l.chars().count())
+            .ok_or_else(|| anyhow!("Empty report"))?;
 
-            co2_ones_cts = get_ones_cts(&co2_lines);
+        if width == 0 {
+            return Err(anyhow!("Empty reading on line 1"));
         }
 
-        if o2_lines.len() == 1 && co2_lines.len() == 1 {
-            break;
+        let radix = alphabet.radix() as u128;
+
+        // The largest reading must fit, so each place value is safe too
+        (0..width)
+            .try_fold(0u128, |max, _| {
+                max.checked_mul(radix)
+                    .and_then(|m| m.checked_add(radix - 1))
+            })
+            .ok_or_else(|| anyhow!("Readings of width {} in base {} are too wide", width, radix))?;
+
+        let place_values = (0..width)
+            .rev()
+            .map(|i| radix.pow(i as u32))
+            .collect::<Vec<_>>();
+
+        let mut readings = Vec::with_capacity(lines.len());
+        let mut trie = Trie::new(alphabet.radix());
+        for (i, line) in lines.iter().enumerate() {
+            let digits = line
+                .chars()
+                .map(|c| {
+                    alphabet
+                        .digit(c)
+                        .ok_or_else(|| anyhow!("Line {} has invalid digit '{}'", i + 1, c))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            if digits.len() != width {
+                return Err(anyhow!(
+                    "Line {} has width {}, expected {}: '{}'",
+                    i + 1,
+                    digits.len(),
+                    width,
+                    line
+                ));
+            }
+
+            trie.insert(digits.iter().copied());
+            readings.push(
+                digits
+                    .iter()
+                    .fold(0, |reading, d| reading * radix + *d as u128),
+            );
         }
+
+        Ok(Self {
+            alphabet,
+            readings,
+            width,
+            place_values,
+            trie,
+        })
+    }
+
+    /// Digit `i` of `reading`, counting from the left as the report is written.
+    fn digit(&self, reading: u128, i: usize) -> usize {
+        (reading / self.place_values[i] % self.alphabet.radix() as u128) as usize
+    }
+
+    fn format(&self, value: u128) -> String {
+        self.alphabet.format(value, self.width)
+    }
+
+    /// The digit `criteria` selects in each position, across every reading.
+    fn common(&self, criteria: &Criteria) -> u128 {
+        (0..self.width).fold(0, |value, i| {
+            let mut counts = vec![0; self.alphabet.radix()];
+            for reading in self.readings.iter() {
+                counts[self.digit(*reading, i)] += 1;
+            }
+
+            let digit = criteria.select(&self.alphabet, &counts, |_| true);
+            value + digit as u128 * self.place_values[i]
+        })
+    }
+
+    fn gamma(&self) -> u128 {
+        self.common(&Criteria::GAMMA)
+    }
+
+    fn epsilon(&self) -> u128 {
+        self.common(&Criteria::EPSILON)
     }
 
-    assert_eq!(1, o2_lines.len());
-    assert_eq!(1, co2_lines.len());
+    fn rating(&self, criteria: &Criteria) -> u128 {
+        self.trie.rating(&self.alphabet, criteria, self.width)
+    }
+
+    /// Products of readings this wide can need up to twice as many bits, so they're computed
+    /// exactly rather than in `u128`.
+    fn power_consumption(&self) -> BigUint {
+        let (gamma, epsilon) = (self.gamma(), self.epsilon());
+
+        debug!("Gamma:  {} ({})", self.format(gamma), gamma);
+        debug!("Epsilon {} ({})", self.format(epsilon), epsilon);
+
+        BigUint::from(gamma) * epsilon
+    }
 
-    let o2 = u32::from_str_radix(&o2_lines[0], 2)?;
-    let co2 = u32::from_str_radix(&co2_lines[0], 2)?;
+    fn life_support_rating(&self, o2: &Criteria, co2: &Criteria) -> BigUint {
+        let (o2, co2) = (self.rating(o2), self.rating(co2));
+
+        debug!("O2 Scrubber Rating:  {} ({})", self.format(o2), o2);
+        debug!("CO2 Scrubber Rating: {} ({})", self.format(co2), co2);
+
+        BigUint::from(o2) * co2
+    }
+}
 
-    debug!("O2 Scubber Rating: {:b} {}", o2, o2);
-    debug!("CO2 Scubber Rating: {:b} {}", co2, co2);
+impl TryFrom<Vec<String>> for DiagnosticReport {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Vec<String>) -> Result<Self, Self::Error> {
+        Self::parse(&value, Alphabet::binary())
+    }
+}
+
+#[derive(Debug)]
+struct Options {
+    alphabet: Alphabet,
+    o2: Criteria,
+    co2: Criteria,
+}
+
+impl Options {
+    /// Parses `[--alphabet SYMBOLS] [--o2 CRITERIA] [--co2 CRITERIA]`, where criteria are
+    /// `most:<tie-break>` or `least:<tie-break>`.
+    fn parse<I: Iterator<Item = String>>(mut args: aoc_utils::Args<I>) -> Result<Self> {
+        let mut options = Self {
+            alphabet: Alphabet::binary(),
+            o2: Criteria::O2,
+            co2: Criteria::CO2,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--alphabet" => options.alphabet = args.parse()?,
+                "--o2" => options.o2 = args.parse()?,
+                "--co2" => options.co2 = args.parse()?,
+                _ => return Err(args.unexpected()),
+            }
+        }
 
-    Ok(o2 * co2)
+        Ok(options)
+    }
 }
 
 fn main() -> Result<()> {
-    env_logger::builder()
-        .target(Target::Stdout)
-        .filter_level(LevelFilter::Info)
-        .try_init()?;
+    aoc_utils::init_logger(LevelFilter::Info)?;
+
+    let options = Options::parse(aoc_utils::args())?;
+    let report = DiagnosticReport::parse(&aoc_utils::read_lines(PATH)?, options.alphabet)?;
 
-    println!("{}", life_support()?);
+    println!("{}", report.power_consumption());
+    println!("{}", report.life_support_rating(&options.o2, &options.co2));
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn report(lines: &[&str]) -> Result<DiagnosticReport> {
+        DiagnosticReport::try_from(self::lines(lines))
+    }
+
+    #[test]
+    fn test_ratings() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let report = report(&[
+            "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000",
+            "11001", "00010", "01010",
+        ])?;
+
+        assert_eq!(22, report.gamma());
+        assert_eq!(9, report.epsilon());
+        assert_eq!(BigUint::from(198u8), report.power_consumption());
+
+        assert_eq!(23, report.rating(&Criteria::O2));
+        assert_eq!(10, report.rating(&Criteria::CO2));
+        assert_eq!(
+            BigUint::from(230u8),
+            report.life_support_rating(&Criteria::O2, &Criteria::CO2)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wide_report() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let ones = "1".repeat(100);
+        let zeros = "0".repeat(100);
+        let report = report(&[&ones, &ones, &zeros])?;
+
+        assert_eq!((1 << 100) - 1, report.gamma());
+        assert_eq!(0, report.epsilon());
+        assert_eq!((1 << 100) - 1, report.rating(&Criteria::O2));
+        assert_eq!(0, report.rating(&Criteria::CO2));
+
+        // Neither product fits in a u128
+        let (high, low) = ("10".repeat(50), "01".repeat(50));
+        let report = self::report(&[&high, &high, &low])?;
+        let (high, low) = (
+            u128::from_str_radix(&high, 2)?,
+            u128::from_str_radix(&low, 2)?,
+        );
+        assert_eq!(BigUint::from(high) * low, report.power_consumption());
+        assert_eq!(
+            BigUint::from(high) * low,
+            report.life_support_rating(&Criteria::O2, &Criteria::CO2)
+        );
+
+        let report = self::report(&[&"1".repeat(128), &"0".repeat(128)])?;
+        assert_eq!(u128::MAX, report.gamma());
+        assert_eq!(
+            BigUint::ZERO,
+            report.life_support_rating(&Criteria::O2, &Criteria::CO2)
+        );
+
+        // The least common bit in a position every reading shares is still that bit
+        let report = self::report(&["0110", "0011"])?;
+        assert_eq!(0b0011, report.rating(&Criteria::CO2));
+        assert_eq!(0b0110, report.rating(&Criteria::O2));
+
+        Ok(())
+    }
+
+    /// Filters the readings one position at a time, as the puzzle describes.
+    fn filter_rating(report: &DiagnosticReport, criteria: &Criteria) -> u128 {
+        let mut readings = report.readings.clone();
+
+        for i in 0..report.width {
+            let mut counts = vec![0; report.alphabet.radix()];
+            for reading in readings.iter() {
+                counts[report.digit(*reading, i)] += 1;
+            }
+
+            let keep = criteria.select(&report.alphabet, &counts, |d| counts[d] > 0);
+            readings.retain(|r| report.digit(*r, i) == keep);
+        }
+
+        readings[0]
+    }
+
+    #[test]
+    fn test_trie_matches_filtering() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        // Deterministic pseudo-random readings, with plenty of duplicates and shared prefixes
+        let mut rng = StdRng::seed_from_u64(3);
+
+        for (alphabet, width, ct) in [
+            ("01", 1, 3),
+            ("01", 5, 20),
+            ("01", 12, 1000),
+            ("01", 40, 500),
+            ("0123", 6, 500),
+            ("0123456789abcdef", 4, 2000),
+        ] {
+            let alphabet = Alphabet::from_str(alphabet)?;
+            let lines = (0..ct)
+                .map(|_| alphabet.format(rng.gen_range(0..1000), width))
+                .collect::<Vec<_>>();
+
+            let report = DiagnosticReport::parse(&lines, alphabet)?;
+            for criteria in [
+                Criteria::O2,
+                Criteria::CO2,
+                Criteria::from_str("most:3")?,
+                Criteria::from_str("least:z")?,
+            ] {
+                assert_eq!(filter_rating(&report, &criteria), report.rating(&criteria));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_alphabets() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let report = DiagnosticReport::parse(
+            &lines(&["ab", "cb", "cd", "ca", "da"]),
+            Alphabet::from_str("abcd")?,
+        )?;
+
+        // Neither default tie-break is in the alphabet, so ties go to the lowest digit. The least
+        // common digits are those no reading has.
+        assert_eq!("ca", report.format(report.gamma()));
+        assert_eq!("bc", report.format(report.epsilon()));
+
+        // 'c' leads in the first position, leaving 'cb', 'cd' and 'ca' tied in the second
+        assert_eq!(
+            "ca",
+            report.format(report.rating(&Criteria::from_str("most:z")?))
+        );
+        assert_eq!(
+            "cd",
+            report.format(report.rating(&Criteria::from_str("most:d")?))
+        );
+
+        // 'a' and 'd' tie for least common in the first position
+        assert_eq!(
+            "ab",
+            report.format(report.rating(&Criteria::from_str("least:a")?))
+        );
+        assert_eq!(
+            "da",
+            report.format(report.rating(&Criteria::from_str("least:d")?))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_reports() -> Result<()> {
+        assert!(report(&[]).is_err());
+        assert!(report(&[""]).is_err());
+        assert!(report(&["0101", "011"]).is_err());
+        assert!(report(&["0121"]).is_err());
+        assert!(report(&[&"1".repeat(129)]).is_err());
+
+        let hex = Alphabet::from_str("0123456789abcdef")?;
+        assert!(DiagnosticReport::parse(&lines(&[&"f".repeat(32)]), hex.clone()).is_ok());
+        assert!(DiagnosticReport::parse(&lines(&[&"f".repeat(33)]), hex).is_err());
+
+        assert!(Alphabet::from_str("0").is_err());
+        assert!(Alphabet::from_str("010").is_err());
+        assert!(Criteria::from_str("most").is_err());
+        assert!(Criteria::from_str("common:1").is_err());
+        assert!(Criteria::from_str("least:01").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+//! Random cards and draw orders, and Monte Carlo estimates of how likely each card is to win.
+
+use super::{Board, Timeline};
+use anyhow::{anyhow, Result};
+use rand::seq::{index, SliceRandom};
+use rand::Rng;
+use std::collections::BTreeMap;
+
+/// Largest `max_value` which can be drawn, as every value in `0..=max_value` is shuffled in memory.
+pub const MAX_DRAWN_VALUE: u32 = 1 << 24;
+
+/// Rows of a `height`x`width` card with distinct values from `0..=max_value`, formatted like the
+/// puzzle input.
+pub fn card<R: Rng>(
+    rng: &mut R,
+    height: usize,
+    width: usize,
+    max_value: u32,
+) -> Result<Vec<String>> {
+    let cells = height * width;
+    if cells == 0 {
+        return Err(anyhow!("Empty card: {}x{}", height, width));
+    }
+    if cells > max_value as usize + 1 {
+        return Err(anyhow!(
+            "A {}x{} card needs {} distinct values, but only 0..={} are available",
+            height,
+            width,
+            cells,
+            max_value
+        ));
+    }
+
+    let values = index::sample(rng, max_value as usize + 1, cells).into_vec();
+    let pad = max_value.to_string().len();
+
+    Ok(values
+        .chunks(width)
+        .map(|row| {
+            row.iter()
+                .map(|v| format!("{:>1$}", v, pad))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect())
+}
+
+/// Every value in `0..=max_value` in random order.
+pub fn draw_order<R: Rng>(rng: &mut R, max_value: u32) -> Result<Vec<u32>> {
+    if max_value > MAX_DRAWN_VALUE {
+        return Err(anyhow!(
+            "Drawing every value up to {} needs too much memory, the most is {}",
+            max_value,
+            MAX_DRAWN_VALUE
+        ));
+    }
+
+    let mut plays = (0..=max_value).collect::<Vec<_>>();
+    plays.shuffle(rng);
+    Ok(plays)
+}
+
+/// Puzzle input for a random game: a draw order followed by `card_ct` cards.
+pub fn game<R: Rng>(
+    rng: &mut R,
+    card_ct: usize,
+    height: usize,
+    width: usize,
+    max_value: u32,
+) -> Result<Vec<String>> {
+    let plays = draw_order(rng, max_value)?
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let mut lines = vec![plays];
+    for _ in 0..card_ct {
+        lines.push(String::new());
+        lines.extend(card(rng, height, width, max_value)?);
+    }
+
+    Ok(lines)
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CardStats {
+    /// Games in which this card was among the first winners.
+    pub wins: usize,
+    /// How many games this card won on each turn.
+    pub turns: BTreeMap<usize, usize>,
+}
+
+impl CardStats {
+    pub fn win_probability(&self, games: usize) -> f64 {
+        self.wins as f64 / games as f64
+    }
+
+    /// Mean turn this card wins on, across the games in which it won at all.
+    pub fn mean_turn(&self) -> Option<f64> {
+        let games = self.turns.values().sum::<usize>();
+        if games == 0 {
+            return None;
+        }
+
+        let total = self.turns.iter().map(|(t, ct)| t * ct).sum::<usize>();
+        Some(total as f64 / games as f64)
+    }
+
+    /// Smallest turn by which this card had won in at least `q` of `games`.
+    pub fn quantile(&self, q: f64, games: usize) -> Option<usize> {
+        let target = (q * games as f64).ceil() as usize;
+
+        let mut seen = 0;
+        for (turn, ct) in self.turns.iter() {
+            seen += ct;
+            if seen >= target.max(1) {
+                return Some(*turn);
+            }
+        }
+
+        None
+    }
+}
+
+/// Plays `games` games with random draw orders from `0..=max_value`, collecting how each board
+/// fares. Cards tied for the first win all count it as a win. Every value on the cards must be
+/// drawable, or those cells would never be marked and the estimates would be skewed.
+pub fn monte_carlo<R: Rng>(
+    rng: &mut R,
+    boards: &[Board],
+    games: usize,
+    max_value: u32,
+) -> Result<Vec<CardStats>> {
+    if games == 0 {
+        return Err(anyhow!("Monte Carlo estimates need at least one game"));
+    }
+
+    let card_max = boards
+        .iter()
+        .flat_map(|b| b.spaces.iter().map(|s| s.value))
+        .max();
+    if let Some(card_max) = card_max.filter(|v| *v > max_value) {
+        return Err(anyhow!(
+            "Cards hold values up to {}, but only 0..={} are drawn, see --max-value",
+            card_max,
+            max_value
+        ));
+    }
+
+    let mut stats = vec![CardStats::default(); boards.len()];
+
+    for _ in 0..games {
+        let timeline = Timeline::solve(boards, &draw_order(rng, max_value)?);
+
+        let first_turn = timeline.first_winner().map(|(_, win)| win.turn);
+        for (stats, win) in stats.iter_mut().zip(timeline.wins.iter()) {
+            if let Some(win) = win {
+                *stats.turns.entry(win.turn).or_default() += 1;
+                if Some(win.turn) == first_turn {
+                    stats.wins += 1;
+                }
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse_boards, parse_plays, WinPattern, DEFAULT_PATTERNS};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_generate() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut lines = game(&mut rng, 3, 4, 6, 99)?;
+
+        // The same seed generates the same game
+        assert_eq!(lines, game(&mut StdRng::seed_from_u64(4), 3, 4, 6, 99)?);
+
+        let mut plays = parse_plays(&lines.remove(0))?;
+        plays.sort_unstable();
+        assert_eq!((0..=99).collect::<Vec<_>>(), plays);
+
+        let boards = parse_boards(&lines, DEFAULT_PATTERNS)?;
+        assert_eq!(3, boards.len());
+        assert!(boards.iter().all(|b| b.width == 6 && b.spaces.len() == 24));
+
+        assert!(card(&mut rng, 5, 5, 23).is_err());
+        assert!(card(&mut rng, 0, 5, 99).is_err());
+        assert_eq!(5, card(&mut rng, 5, 5, 24)?.len());
+
+        // Every value is shuffled in memory, so huge ranges are rejected up front
+        assert!(draw_order(&mut rng, MAX_DRAWN_VALUE + 1).is_err());
+        assert!(game(&mut rng, 3, 5, 5, 4_000_000_000).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_monte_carlo() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let mut rng = StdRng::seed_from_u64(2021);
+        let boards = [
+            Board::parse(&["1 2".to_string(), "3 4".to_string()], DEFAULT_PATTERNS)?,
+            Board::parse(&["5 6 7".to_string()], &[WinPattern::FullCard])?,
+        ];
+
+        let games = 1000;
+        let stats = monte_carlo(&mut rng, &boards, games, 9)?;
+
+        // Every board eventually wins when every value is drawn
+        for stats in stats.iter() {
+            assert_eq!(games, stats.turns.values().sum::<usize>());
+            assert!(stats.quantile(1.0, games).unwrap() <= 10);
+        }
+
+        // Two of four cells finish a line well before three specific values are all drawn
+        assert!(stats[0].win_probability(games) > stats[1].win_probability(games));
+        assert!(stats[0].mean_turn() < stats[1].mean_turn());
+        assert!(stats[0].wins + stats[1].wins >= games);
+
+        // Values which can't be drawn, or no games at all
+        assert!(monte_carlo(&mut rng, &boards, games, 6).is_err());
+        assert!(monte_carlo(&mut rng, &boards, 0, 9).is_err());
+
+        Ok(())
+    }
+}
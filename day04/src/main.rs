@@ -1,10 +1,15 @@
 use anyhow::{anyhow, Result};
-use log::{debug, LevelFilter};
+use log::{debug, info, LevelFilter};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use std::collections::HashMap;
 use std::fmt;
 use std::str::FromStr;
 
-#[derive(Default)]
+mod generate;
+mod server;
+
+#[derive(Default, Clone)]
 struct Space {
     value: u32,
     marked: bool,
@@ -30,18 +35,223 @@ impl fmt::Debug for Space {
     }
 }
 
-#[derive(Default)]
+/// A set of cells which wins the board once every one of them is marked.
+type Line = Vec<(usize, usize)>;
+
+/// Rules deciding which lines of a board win.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum WinPattern {
+    Rows,
+    Columns,
+    /// Both main diagonals, on square boards only.
+    Diagonals,
+    FourCorners,
+    FullCard,
+    /// Custom shape as rows of cells, where `true` cells must be marked.
+    Mask(Vec<Vec<bool>>),
+}
+
+const DEFAULT_PATTERNS: &[WinPattern] = &[WinPattern::Rows, WinPattern::Columns];
+
+impl WinPattern {
+    fn lines(&self, height: usize, width: usize) -> Result<Vec<Line>> {
+        let lines = match self {
+            WinPattern::Rows => (0..height)
+                .map(|x| (0..width).map(|y| (x, y)).collect())
+                .collect(),
+            WinPattern::Columns => (0..width)
+                .map(|y| (0..height).map(|x| (x, y)).collect())
+                .collect(),
+            WinPattern::Diagonals => {
+                if height != width {
+                    return Err(anyhow!(
+                        "Diagonals need a square board, not {}x{}",
+                        height,
+                        width
+                    ));
+                }
+
+                vec![
+                    (0..height).map(|i| (i, i)).collect(),
+                    (0..height).map(|i| (i, width - 1 - i)).collect(),
+                ]
+            }
+            WinPattern::FourCorners => {
+                let mut corners = vec![
+                    (0, 0),
+                    (0, width - 1),
+                    (height - 1, 0),
+                    (height - 1, width - 1),
+                ];
+                corners.sort_unstable();
+                corners.dedup();
+                vec![corners]
+            }
+            WinPattern::FullCard => vec![(0..height)
+                .flat_map(|x| (0..width).map(move |y| (x, y)))
+                .collect()],
+            WinPattern::Mask(mask) => {
+                if mask.len() != height || mask.iter().any(|row| row.len() != width) {
+                    return Err(anyhow!(
+                        "Mask {} doesn't fit a {}x{} board",
+                        self,
+                        height,
+                        width
+                    ));
+                }
+
+                vec![mask
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(x, row)| {
+                        row.iter()
+                            .enumerate()
+                            .filter(|(_, required)| **required)
+                            .map(move |(y, _)| (x, y))
+                    })
+                    .collect()]
+            }
+        };
+
+        Ok(lines)
+    }
+}
+
+impl FromStr for WinPattern {
+    type Err = anyhow::Error;
+
+    /// Parses `rows`, `columns`, `diagonals`, `corners`, `full`, or `mask=<rows>`, where mask rows
+    /// are separated by `/` and use `#` for required cells and `.` for the rest, e.g.
+    /// `mask=#..#/.##./.##./#..#`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rows" => Ok(WinPattern::Rows),
+            "columns" => Ok(WinPattern::Columns),
+            "diagonals" => Ok(WinPattern::Diagonals),
+            "corners" => Ok(WinPattern::FourCorners),
+            "full" => Ok(WinPattern::FullCard),
+            _ => {
+                let mask = s
+                    .strip_prefix("mask=")
+                    .ok_or_else(|| anyhow!("Unknown win pattern: '{}'", s))?;
+
+                let mask = mask
+                    .split('/')
+                    .map(|row| {
+                        row.chars()
+                            .map(|c| match c {
+                                '#' => Ok(true),
+                                '.' => Ok(false),
+                                _ => Err(anyhow!("Invalid mask cell '{}' in '{}'", c, s)),
+                            })
+                            .collect::<Result<Vec<_>>>()
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                if !mask.iter().flatten().any(|required| *required) {
+                    return Err(anyhow!("Mask has no required cells: '{}'", s));
+                }
+
+                Ok(WinPattern::Mask(mask))
+            }
+        }
+    }
+}
+
+impl fmt::Display for WinPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WinPattern::Rows => write!(f, "rows"),
+            WinPattern::Columns => write!(f, "columns"),
+            WinPattern::Diagonals => write!(f, "diagonals"),
+            WinPattern::FourCorners => write!(f, "corners"),
+            WinPattern::FullCard => write!(f, "full"),
+            WinPattern::Mask(mask) => write!(
+                f,
+                "mask={}",
+                mask.iter()
+                    .map(|row| row.iter().map(|c| if *c { '#' } else { '.' }).collect())
+                    .collect::<Vec<String>>()
+                    .join("/")
+            ),
+        }
+    }
+}
+
 struct Board {
-    board: [[Space; 5]; 5],
+    width: usize,
+    spaces: Vec<Space>,
     position_map: HashMap<u32, (usize, usize)>,
+    lines: Vec<Line>,
     winning_play: Option<u32>,
 }
 
 impl Board {
+    /// Parses a board from rows of whitespace-separated numbers, which win under any of
+    /// `patterns`.
+    fn parse(rows: &[String], patterns: &[WinPattern]) -> Result<Self> {
+        let mut spaces = Vec::new();
+        let mut position_map = HashMap::new();
+        let mut width = None;
+
+        for (x, line) in rows.iter().enumerate() {
+            let row = line
+                .split_ascii_whitespace()
+                .map(u32::from_str)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            match width {
+                None => width = Some(row.len()),
+                Some(width) if width != row.len() => {
+                    return Err(anyhow!(
+                        "Row {} has {} values, expected {}: '{}'",
+                        x,
+                        row.len(),
+                        width,
+                        line
+                    ))
+                }
+                _ => (),
+            }
+
+            for (y, space_val) in row.into_iter().enumerate() {
+                let prev = position_map.insert(space_val, (x, y));
+                if prev.is_some() {
+                    return Err(anyhow!("Duplicate value: {}", space_val));
+                }
+
+                spaces.push(space_val.into());
+            }
+        }
+
+        let (height, width) = match width {
+            Some(width) if width > 0 => (rows.len(), width),
+            _ => return Err(anyhow!("Empty board")),
+        };
+
+        let lines = patterns
+            .iter()
+            .map(|p| p.lines(height, width))
+            .collect::<Result<Vec<_>>>()?
+            .concat();
+
+        Ok(Self {
+            width,
+            spaces,
+            position_map,
+            lines,
+            winning_play: None,
+        })
+    }
+
+    fn space(&self, x: usize, y: usize) -> &Space {
+        &self.spaces[x * self.width + y]
+    }
+
     fn mark(&mut self, value: u32) -> bool {
         if !self.won() {
             if let Some((x, y)) = self.position_map.get(&value) {
-                self.board[*x][*y].marked = true;
+                self.spaces[x * self.width + y].marked = true;
 
                 if self.check_win_conditions() {
                     self.winning_play = Some(value);
@@ -57,35 +267,40 @@ impl Board {
     }
 
     fn check_win_conditions(&self) -> bool {
-        for row in &self.board {
-            if row.iter().all(|s| s.marked) {
-                return true;
-            }
-        }
+        self.completed_line().is_some()
+    }
 
-        for col in 0..self.board.len() {
-            if self.board.iter().all(|s| s[col].marked) {
-                return true;
-            }
-        }
+    /// Index of the first line whose cells are all marked.
+    fn completed_line(&self) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|line| line.iter().all(|(x, y)| self.space(*x, *y).marked))
+    }
 
-        false
+    /// The line which won the board, as (row, column) cells. Marking stops once the board has
+    /// won, so this is the line completed by the winning play.
+    fn winning_line(&self) -> Option<&Line> {
+        self.winning_play
+            .and(self.completed_line())
+            .map(|i| &self.lines[i])
     }
 
-    fn value(&self) -> u32 {
+    fn value(&self) -> u128 {
         assert!(self.won());
 
-        let unmarked_sum: u32 = self
-            .board
+        let unmarked_sum: u128 = self
+            .spaces
             .iter()
-            .map(|row| {
-                row.iter()
-                    .filter_map(|s| if s.marked { None } else { Some(s.value) })
-                    .sum::<u32>()
+            .filter_map(|s| {
+                if s.marked {
+                    None
+                } else {
+                    Some(s.value as u128)
+                }
             })
             .sum();
 
-        unmarked_sum * self.winning_play.unwrap()
+        unmarked_sum * self.winning_play.unwrap() as u128
     }
 }
 
@@ -93,29 +308,14 @@ impl TryFrom<&[String]> for Board {
     type Error = anyhow::Error;
 
     fn try_from(value: &[String]) -> Result<Self, Self::Error> {
-        let mut board = Board::default();
-
-        for (x, line) in value.iter().enumerate() {
-            for (y, val) in line.split_ascii_whitespace().enumerate() {
-                let space_val = u32::from_str(val)?;
-
-                let prev = board.position_map.insert(space_val, (x, y));
-                if prev.is_some() {
-                    return Err(anyhow!("Duplicate value: {}", space_val));
-                }
-
-                board.board[x][y] = space_val.into();
-            }
-        }
-
-        Ok(board)
+        Board::parse(value, DEFAULT_PATTERNS)
     }
 }
 
 impl fmt::Debug for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "{{")?;
-        for row in &self.board {
+        for row in self.spaces.chunks(self.width) {
             writeln!(f, "\t{:?}", row)?;
         }
         write!(f, "}}")?;
@@ -124,39 +324,308 @@ impl fmt::Debug for Board {
     }
 }
 
+fn parse_plays(line: &str) -> Result<Vec<u32>> {
+    line.split(',')
+        .map(|s| u32::from_str(s.trim()).map_err(|e| anyhow!("Invalid play '{}': {}", s, e)))
+        .collect()
+}
+
+/// Parses boards from blocks of rows separated by blank lines.
+fn parse_boards(lines: &[String], patterns: &[WinPattern]) -> Result<Vec<Board>> {
+    lines
+        .split(|line| line.trim().is_empty())
+        .filter(|block| !block.is_empty())
+        .map(|block| Board::parse(block, patterns))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Win {
+    /// Number of plays drawn when the board won.
+    turn: usize,
+    play: u32,
+    /// Wide enough that it can't overflow, as it's a sum of up to `usize::MAX` u32 values times
+    /// a u32 play.
+    score: u128,
+    line: Line,
+}
+
+/// The outcome of a game for every board, indexed like the boards.
+#[derive(Debug)]
+struct Timeline {
+    wins: Vec<Option<Win>>,
+}
+
+impl Timeline {
+    /// Draws every play in turn, marking each board which hasn't won yet.
+    fn play(boards: &mut [Board], plays: &[u32]) -> Self {
+        let mut wins = vec![None; boards.len()];
+
+        for (turn, play) in plays.iter().enumerate() {
+            for (board_idx, board) in boards.iter_mut().enumerate() {
+                if board.mark(*play) {
+                    wins[board_idx] = Some(Win {
+                        turn: turn + 1,
+                        play: *play,
+                        score: board.value(),
+                        line: board.winning_line().unwrap().clone(),
+                    });
+                }
+            }
+        }
+
+        Self { wins }
+    }
+
+    /// Finds when each board wins without marking them, from the turn each number is drawn: a
+    /// line completes on the latest turn of its cells, and a board wins on the earliest turn of
+    /// its lines. Boards are only read, so their marks are ignored.
+    fn solve(boards: &[Board], plays: &[u32]) -> Self {
+        let mut turns = HashMap::new();
+        for (turn, play) in plays.iter().enumerate() {
+            turns.entry(*play).or_insert(turn + 1);
+        }
+
+        let wins = boards
+            .iter()
+            .map(|board| {
+                let turn = |(x, y): &(usize, usize)| turns.get(&board.space(*x, *y).value);
+
+                let (turn, line) = board
+                    .lines
+                    .iter()
+                    .filter_map(|line| {
+                        line.iter()
+                            .map(turn)
+                            .collect::<Option<Vec<_>>>()
+                            .map(|turns| (*turns.into_iter().max().unwrap(), line))
+                    })
+                    .min_by_key(|(turn, _)| *turn)?;
+
+                let unmarked_sum: u128 = board
+                    .spaces
+                    .iter()
+                    .filter(|s| turns.get(&s.value).is_none_or(|t| *t > turn))
+                    .map(|s| s.value as u128)
+                    .sum();
+                let play = plays[turn - 1];
+
+                Some(Win {
+                    turn,
+                    play,
+                    score: unmarked_sum * play as u128,
+                    line: line.clone(),
+                })
+            })
+            .collect();
+
+        Self { wins }
+    }
+
+    /// Boards which won, in the order they won. Boards winning on the same turn are ordered by
+    /// index.
+    fn ranking(&self) -> Vec<(usize, &Win)> {
+        let mut ranking = self
+            .wins
+            .iter()
+            .enumerate()
+            .filter_map(|(i, win)| win.as_ref().map(|win| (i, win)))
+            .collect::<Vec<_>>();
+        ranking.sort_by_key(|(i, win)| (win.turn, *i));
+        ranking
+    }
+
+    fn first_winner(&self) -> Option<(usize, &Win)> {
+        self.ranking().first().copied()
+    }
+
+    fn last_winner(&self) -> Option<(usize, &Win)> {
+        self.ranking().last().copied()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Solver {
+    /// Marks every play on every board, see `Timeline::play`.
+    Simulate,
+    /// Derives win turns from draw order, see `Timeline::solve`.
+    Precompute,
+}
+
+impl FromStr for Solver {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "simulate" => Ok(Solver::Simulate),
+            "precompute" => Ok(Solver::Precompute),
+            _ => Err(anyhow!("Unknown solver: '{}'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    /// Finds the first and last winners of the input.
+    Solve,
+    /// Prints a random game with this many cards.
+    Generate(usize),
+    /// Estimates how the input's cards fare over this many random draw orders.
+    MonteCarlo(usize),
+    /// Hosts the game for players connecting to this loopback address, see `server`.
+    Serve(String),
+}
+
+struct Options {
+    patterns: Vec<WinPattern>,
+    solver: Solver,
+    mode: Mode,
+    /// Plays a random game with this many cards instead of the input.
+    cards: Option<usize>,
+    seed: u64,
+    height: usize,
+    width: usize,
+    max_value: u32,
+}
+
+impl Options {
+    /// Parses `[--patterns PATTERN,...] [--solver simulate|precompute] [--generate CARDS]
+    /// [--monte-carlo GAMES] [--serve PORT|ADDR] [--cards N] [--seed SEED] [--size HxW]
+    /// [--max-value N]`, see
+    /// `WinPattern::from_str` for the pattern syntax.
+    fn parse<I: Iterator<Item = String>>(mut args: aoc_utils::Args<I>) -> Result<Self> {
+        let mut options = Self {
+            patterns: DEFAULT_PATTERNS.to_vec(),
+            solver: Solver::Precompute,
+            mode: Mode::Solve,
+            cards: None,
+            seed: 2021,
+            height: 5,
+            width: 5,
+            max_value: 99,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--patterns" => {
+                    options.patterns = args
+                        .value()?
+                        .split(',')
+                        .map(WinPattern::from_str)
+                        .collect::<Result<_>>()?
+                }
+                "--solver" => options.solver = args.parse()?,
+                "--generate" => options.mode = Mode::Generate(args.parse()?),
+                "--monte-carlo" => options.mode = Mode::MonteCarlo(args.parse()?),
+                "--serve" => {
+                    // A bare port listens on localhost
+                    let addr = args.value()?;
+                    options.mode = Mode::Serve(match u16::from_str(&addr) {
+                        Ok(port) => format!("127.0.0.1:{}", port),
+                        Err(_) => addr,
+                    });
+                }
+                "--cards" => options.cards = Some(args.parse()?),
+                "--seed" => options.seed = args.parse()?,
+                "--size" => {
+                    let value = args.value()?;
+                    let (height, width) = value
+                        .split_once('x')
+                        .ok_or_else(|| anyhow!("Invalid size: '{}'", value))?;
+                    options.height = usize::from_str(height)?;
+                    options.width = usize::from_str(width)?;
+                }
+                "--max-value" => options.max_value = args.parse()?,
+                _ => return Err(args.unexpected()),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
 fn main() -> Result<()> {
     aoc_utils::init_logger(LevelFilter::Info)?;
 
-    let mut lines = aoc_utils::input()?;
+    let options = Options::parse(aoc_utils::args())?;
+    let mut rng = StdRng::seed_from_u64(options.seed);
+
+    if let Mode::Generate(card_ct) = options.mode {
+        for line in generate::game(
+            &mut rng,
+            card_ct,
+            options.height,
+            options.width,
+            options.max_value,
+        )? {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    let mut lines = match options.cards {
+        Some(card_ct) => generate::game(
+            &mut rng,
+            card_ct,
+            options.height,
+            options.width,
+            options.max_value,
+        )?,
+        None => aoc_utils::input()?,
+    };
 
     // First line is the plays
-    let plays: Vec<u32> = lines
-        .remove(0)
-        .split(',')
-        .map(|s| u32::from_str(s).unwrap())
-        .collect();
+    let plays = parse_plays(&lines.remove(0))?;
     debug!("Plays: {:?}", plays);
 
-    let mut boards = Vec::new();
-    // Skip the second empty line and advance by 6 to account for the 5x5 board plus a newline
-    for i in (1..lines.len()).into_iter().step_by(6) {
-        let board = Board::try_from(&lines[i..i + 5])?;
+    let mut boards = parse_boards(&lines, &options.patterns)?;
+    for board in boards.iter() {
         debug!("{:?}", board);
-        boards.push(board);
     }
 
-    let mut last_winner: Option<usize> = None;
-    for play in plays.into_iter() {
-        for (board_idx, board) in boards.iter_mut().enumerate() {
-            if board.mark(play) {
-                last_winner = Some(board_idx);
-            }
+    if let Mode::Serve(addr) = options.mode {
+        let server = server::Server::bind(addr, boards, plays)?;
+        info!("Listening on {}", server.local_addr()?);
+        return server.run();
+    }
+
+    if let Mode::MonteCarlo(games) = options.mode {
+        let stats = generate::monte_carlo(&mut rng, &boards, games, options.max_value)?;
+        for (board_idx, stats) in stats.iter().enumerate() {
+            println!(
+                "Board {}: p(win)={:.3} mean_turn={} p50={} p90={}",
+                board_idx,
+                stats.win_probability(games),
+                stats
+                    .mean_turn()
+                    .map_or("-".to_string(), |t| format!("{:.1}", t)),
+                stats
+                    .quantile(0.5, games)
+                    .map_or("-".to_string(), |t| t.to_string()),
+                stats
+                    .quantile(0.9, games)
+                    .map_or("-".to_string(), |t| t.to_string()),
+            );
+            debug!("Board {} win turns: {:?}", board_idx, stats.turns);
         }
+        return Ok(());
+    }
+
+    let timeline = match options.solver {
+        Solver::Simulate => Timeline::play(&mut boards, &plays),
+        Solver::Precompute => Timeline::solve(&boards, &plays),
+    };
+    for (board_idx, win) in timeline.wins.iter().enumerate() {
+        debug!("Board {}: {:?}", board_idx, win);
     }
 
-    assert!(last_winner.is_some());
-    let last_winning_board = &boards[last_winner.unwrap()];
-    println!("{}", last_winning_board.value());
+    let (_, first) = timeline
+        .first_winner()
+        .ok_or_else(|| anyhow!("No board won"))?;
+    let (_, last) = timeline.last_winner().unwrap();
+
+    println!("{}", first.score);
+    println!("{}", last.score);
 
     Ok(())
 }
@@ -241,4 +710,221 @@ mod test {
 
         Ok(())
     }
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn square() -> Vec<String> {
+        lines(&["1 2 3", "4 5 6", "7 8 9"])
+    }
+
+    fn won_after(board: &[String], pattern: &str, plays: &[u32]) -> Result<bool> {
+        let mut board = Board::parse(board, &[WinPattern::from_str(pattern)?])?;
+        Ok(plays.iter().any(|play| board.mark(*play)))
+    }
+
+    #[test]
+    fn test_board_dimensions() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let mut board = Board::try_from(lines(&["1 2 3 4", "5 6 7 8"]).as_ref())?;
+        assert_eq!((2, 4), (board.spaces.len() / board.width, board.width));
+
+        assert!(!board.mark(1));
+        assert!(board.mark(5));
+        assert_eq!(5 * (2 + 3 + 4 + 6 + 7 + 8), board.value());
+
+        assert!(Board::try_from(lines(&["1 2", "3"]).as_ref()).is_err());
+        assert!(Board::try_from(lines(&["1 2", "3 1"]).as_ref()).is_err());
+        assert!(Board::try_from(lines(&[]).as_ref()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_boards() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let boards = parse_boards(
+            &lines(&["", "1 2", "3 4", "", "", "5 6 7", "8 9 10", "11 12 13", ""]),
+            DEFAULT_PATTERNS,
+        )?;
+
+        assert_eq!(2, boards.len());
+        assert_eq!((4, 2), (boards[0].spaces.len(), boards[0].width));
+        assert_eq!((9, 3), (boards[1].spaces.len(), boards[1].width));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_win_patterns() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let board = square();
+
+        assert!(won_after(&board, "diagonals", &[1, 5, 9])?);
+        assert!(won_after(&board, "diagonals", &[3, 5, 7])?);
+        assert!(!won_after(&board, "diagonals", &[1, 2, 3])?);
+
+        assert!(won_after(&board, "corners", &[1, 3, 7, 9])?);
+        assert!(!won_after(&board, "corners", &[1, 3, 5, 7])?);
+
+        assert!(!won_after(&board, "full", &[1, 2, 3, 4, 5, 6, 7, 8])?);
+        assert!(won_after(&board, "full", &[1, 2, 3, 4, 5, 6, 7, 8, 9])?);
+
+        assert!(won_after(&board, "mask=.#./###/.#.", &[2, 4, 5, 6, 8])?);
+        assert!(!won_after(&board, "mask=.#./###/.#.", &[1, 3, 5, 7, 9])?);
+
+        assert!(Board::parse(&lines(&["1 2 3"]), &[WinPattern::Diagonals]).is_err());
+        assert!(Board::parse(&board, &[WinPattern::from_str("mask=##/##")?]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_patterns() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        for pattern in [
+            "rows",
+            "columns",
+            "diagonals",
+            "corners",
+            "full",
+            "mask=#..#/.##.",
+        ] {
+            assert_eq!(pattern, WinPattern::from_str(pattern)?.to_string());
+        }
+
+        assert!(WinPattern::from_str("stripes").is_err());
+        assert!(WinPattern::from_str("mask=#x/..").is_err());
+        assert!(WinPattern::from_str("mask=../..").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timeline() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let plays =
+            parse_plays("7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1")?;
+        let mut boards = parse_boards(
+            &lines(&[
+                "22 13 17 11  0",
+                " 8  2 23  4 24",
+                "21  9 14 16  7",
+                " 6 10  3 18  5",
+                " 1 12 20 15 19",
+                "",
+                " 3 15  0  2 22",
+                " 9 18 13 17  5",
+                "19  8  7 25 23",
+                "20 11 10 24  4",
+                "14 21 16 12  6",
+                "",
+                "14 21 17 24  4",
+                "10 16 15  9 19",
+                "18  8 23 26 20",
+                "22 11 13  6  5",
+                " 2  0 12  3  7",
+            ]),
+            DEFAULT_PATTERNS,
+        )?;
+
+        let timeline = Timeline::play(&mut boards, &plays);
+        assert_eq!(timeline.wins, Timeline::solve(&boards, &plays).wins);
+
+        let (board_idx, first) = timeline.first_winner().unwrap();
+        assert_eq!(2, board_idx);
+        assert_eq!((12, 24, 4512), (first.turn, first.play, first.score));
+        assert_eq!(vec![(0, 0), (0, 1), (0, 2), (0, 3), (0, 4)], first.line);
+        assert_eq!(Some(&first.line), boards[2].winning_line());
+
+        let (board_idx, last) = timeline.last_winner().unwrap();
+        assert_eq!(1, board_idx);
+        assert_eq!((15, 13, 1924), (last.turn, last.play, last.score));
+
+        assert_eq!(
+            vec![2, 0, 1],
+            timeline
+                .ranking()
+                .into_iter()
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_winner() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let mut boards = vec![Board::try_from(lines(&["1 2", "3 4"]).as_ref())?];
+        let timeline = Timeline::play(&mut boards, &parse_plays("1,4")?);
+
+        assert_eq!(vec![None], timeline.wins);
+        assert!(timeline.first_winner().is_none());
+        assert!(boards[0].winning_line().is_none());
+
+        assert!(parse_plays("1,,2").is_err());
+        assert!(parse_plays("1,-2").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_large_scores() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let max = u32::MAX;
+        let mut boards = vec![Board::try_from(
+            lines(&[&format!("{} {}", max, max - 1), &format!("{} 0", max - 2)]).as_ref(),
+        )?];
+        let plays = [max, max - 1];
+
+        let timeline = Timeline::play(&mut boards, &plays);
+        assert_eq!(timeline.wins, Timeline::solve(&boards, &plays).wins);
+        assert_eq!(
+            (max - 2) as u128 * (max - 1) as u128,
+            timeline.first_winner().unwrap().1.score
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_solve_matches_play() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let board = lines(&["1 2 3 4", "5 6 7 8", "9 10 11 12", "13 14 15 16"]);
+        let plays = parse_plays("6,1,16,11,3,7,2,9,13,4,10,5,15,12,14,8,6")?;
+
+        for patterns in [
+            "rows,columns",
+            "diagonals",
+            "corners",
+            "full",
+            "mask=.##./#..#/#..#/.##.",
+            "rows,diagonals,corners",
+        ] {
+            let patterns = patterns
+                .split(',')
+                .map(WinPattern::from_str)
+                .collect::<Result<Vec<_>>>()?;
+
+            for n in 0..=plays.len() {
+                let mut boards = vec![Board::parse(&board, &patterns)?];
+                let solved = Timeline::solve(&boards, &plays[..n]);
+                let played = Timeline::play(&mut boards, &plays[..n]);
+
+                assert_eq!(played.wins, solved.wins, "{:?} after {}", patterns, n);
+            }
+        }
+
+        Ok(())
+    }
 }
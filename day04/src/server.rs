@@ -0,0 +1,450 @@
+//! A bingo server for playing over TCP with a line protocol. Each player sends commands and
+//! receives replies, while draws and winners are announced to everyone:
+//!
+//! * `JOIN <name>` takes the next card, replying `OK`
+//! * `CARD` replies `CARD <rows>`, with rows separated by `/`
+//! * `DRAW` announces `DRAWN <n>` for the next play
+//! * `BINGO` announces `WINNER <name> <score>` if the player's card has won
+//! * `QUIT` replies `BYE` and disconnects
+//!
+//! Failed commands reply `ERR <reason>`.
+//!
+//! Each connection's lines are written by a thread of its own from a bounded queue, so a player
+//! who stops reading can't hold up everyone else: once their queue fills up they're dropped and
+//! disconnected. A player's card goes back to be handed out again when they leave, unless it has
+//! already won. The server only listens on loopback addresses.
+
+use super::Board;
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Lines which may be waiting to be written to a connection before its player is dropped.
+const QUEUE_LEN: usize = 256;
+/// How long a write may block before the connection is given up on.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Player {
+    name: String,
+    board: Board,
+    sender: SyncSender<String>,
+    /// Shut down for reading once the player is removed, which ends their connection.
+    stream: TcpStream,
+    claimed: bool,
+}
+
+struct Game {
+    /// Cards not yet taken, in the order they're handed out.
+    cards: Vec<Board>,
+    plays: Vec<u32>,
+    drawn: usize,
+    players: BTreeMap<usize, Player>,
+    next_id: usize,
+}
+
+impl Game {
+    /// Queues `message` for every player, dropping those who aren't keeping up.
+    fn broadcast(&mut self, message: &str) {
+        let behind = self
+            .players
+            .iter()
+            .filter(|(_, player)| player.sender.try_send(message.to_string()).is_err())
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        for id in behind {
+            if let Some(name) = self.remove(id) {
+                warn!("Dropped {}, who stopped reading", name);
+            }
+        }
+    }
+
+    /// Removes a player and returns their name. Their card is handed out again unless it has
+    /// already won, so it can't be claimed twice, and no more of their commands are read.
+    fn remove(&mut self, id: usize) -> Option<String> {
+        let player = self.players.remove(&id)?;
+        if !player.board.won() {
+            self.cards.insert(0, player.board);
+        }
+        let _ = player.stream.shutdown(Shutdown::Read);
+
+        Some(player.name)
+    }
+
+    fn player(&mut self, id: Option<usize>) -> Result<&mut Player> {
+        id.and_then(|id| self.players.get_mut(&id))
+            .ok_or_else(|| anyhow!("Join first"))
+    }
+
+    /// Runs a command from the player `id`, returning the reply for them alone, if any.
+    fn execute(
+        &mut self,
+        id: &mut Option<usize>,
+        line: &str,
+        sender: &SyncSender<String>,
+        stream: &TcpStream,
+    ) -> Result<Option<String>> {
+        let (command, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+
+        match command {
+            "JOIN" => {
+                if id.is_some() {
+                    return Err(anyhow!("Already joined"));
+                }
+                let name = args.trim();
+                if name.is_empty() {
+                    return Err(anyhow!("Missing name"));
+                }
+                if self.cards.is_empty() {
+                    return Err(anyhow!("No cards left"));
+                }
+
+                let mut board = self.cards.remove(0);
+                for play in self.plays[..self.drawn].iter() {
+                    board.mark(*play);
+                }
+
+                self.players.insert(
+                    self.next_id,
+                    Player {
+                        name: name.to_string(),
+                        board,
+                        sender: sender.clone(),
+                        stream: stream.try_clone()?,
+                        claimed: false,
+                    },
+                );
+                *id = Some(self.next_id);
+                self.next_id += 1;
+
+                info!("{} joined", name);
+                Ok(Some("OK".to_string()))
+            }
+            "CARD" => {
+                let board = &self.player(*id)?.board;
+                let rows = board
+                    .spaces
+                    .chunks(board.width)
+                    .map(|row| {
+                        row.iter()
+                            .map(|s| s.value.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    })
+                    .collect::<Vec<_>>();
+
+                Ok(Some(format!("CARD {}", rows.join("/"))))
+            }
+            "DRAW" => {
+                self.player(*id)?;
+                let play = *self
+                    .plays
+                    .get(self.drawn)
+                    .ok_or_else(|| anyhow!("No plays left"))?;
+                self.drawn += 1;
+
+                for player in self.players.values_mut() {
+                    player.board.mark(play);
+                }
+
+                debug!("Drew {}", play);
+                self.broadcast(&format!("DRAWN {}", play));
+                Ok(None)
+            }
+            "BINGO" => {
+                let player = self.player(*id)?;
+                if player.claimed {
+                    return Err(anyhow!("Already claimed"));
+                }
+                if !player.board.check_win_conditions() {
+                    return Err(anyhow!("No bingo"));
+                }
+                player.claimed = true;
+
+                let message = format!("WINNER {} {}", player.name, player.board.value());
+                info!("{}", message);
+                self.broadcast(&message);
+                Ok(None)
+            }
+            _ => Err(anyhow!("Unknown command: '{}'", command)),
+        }
+    }
+}
+
+/// Writes queued lines to `stream` until every sender is gone or a write fails, then closes the
+/// connection.
+fn write_lines(mut stream: TcpStream, receiver: Receiver<String>) {
+    for line in receiver {
+        if let Err(e) = writeln!(stream, "{}", line) {
+            debug!("Failed to write to {:?}: {}", stream.peer_addr(), e);
+            break;
+        }
+    }
+
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+fn handle(game: Arc<Mutex<Game>>, stream: TcpStream) -> Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let reader = BufReader::new(stream.try_clone()?);
+
+    let (sender, receiver) = mpsc::sync_channel(QUEUE_LEN);
+    let writer = stream.try_clone()?;
+    thread::spawn(move || write_lines(writer, receiver));
+
+    let mut id = None;
+    let mut quit = false;
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim() == "QUIT" {
+            quit = true;
+            break;
+        }
+
+        // Queue replies while holding the lock so they stay in order with announcements
+        let mut game = game.lock().unwrap();
+        let reply = match game.execute(&mut id, &line, &sender, &stream) {
+            Ok(reply) => reply,
+            Err(e) => Some(format!("ERR {}", e)),
+        };
+        if let Some(reply) = reply {
+            if sender.try_send(reply).is_err() {
+                break;
+            }
+        }
+    }
+
+    if let Some(name) = id.and_then(|id| game.lock().unwrap().remove(id)) {
+        info!("{} left", name);
+    }
+    if quit {
+        let _ = sender.try_send("BYE".to_string());
+    }
+
+    Ok(())
+}
+
+pub struct Server {
+    listener: TcpListener,
+    game: Arc<Mutex<Game>>,
+}
+
+impl Server {
+    /// Listens on `addr`, which must be a loopback address, handing out `boards` to players as
+    /// they join and drawing `plays` in order.
+    pub fn bind<A: ToSocketAddrs>(addr: A, boards: Vec<Board>, plays: Vec<u32>) -> Result<Self> {
+        let addrs = addr.to_socket_addrs()?.collect::<Vec<_>>();
+        if let Some(addr) = addrs.iter().find(|a| !a.ip().is_loopback()) {
+            return Err(anyhow!("Only loopback addresses are supported: {}", addr));
+        }
+
+        Ok(Self {
+            listener: TcpListener::bind(&addrs[..])?,
+            game: Arc::new(Mutex::new(Game {
+                cards: boards,
+                plays,
+                drawn: 0,
+                players: BTreeMap::new(),
+                next_id: 0,
+            })),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Serves each connection on its own thread, forever.
+    pub fn run(self) -> Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let game = self.game.clone();
+
+            thread::spawn(move || {
+                if let Err(e) = handle(game, stream) {
+                    warn!("Connection failed: {}", e);
+                }
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::DEFAULT_PATTERNS;
+    use std::io::Read;
+    use std::time::Duration;
+
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Client {
+        fn connect(addr: SocketAddr) -> Result<Self> {
+            let stream = TcpStream::connect(addr)?;
+            stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+            Ok(Self {
+                reader: BufReader::new(stream.try_clone()?),
+                writer: stream,
+            })
+        }
+
+        fn send(&mut self, line: &str) -> Result<()> {
+            writeln!(self.writer, "{}", line)?;
+            Ok(())
+        }
+
+        fn recv(&mut self) -> Result<String> {
+            let mut line = String::new();
+            self.reader.read_line(&mut line)?;
+            Ok(line.trim_end().to_string())
+        }
+
+        fn request(&mut self, line: &str) -> Result<String> {
+            self.send(line)?;
+            self.recv()
+        }
+    }
+
+    fn board(rows: &[&str]) -> Result<Board> {
+        let rows = rows.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        Board::parse(&rows, DEFAULT_PATTERNS)
+    }
+
+    #[test]
+    fn test_game() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let server = Server::bind(
+            "127.0.0.1:0",
+            vec![board(&["1 2", "3 4"])?, board(&["5 6", "7 8"])?],
+            vec![1, 5, 2],
+        )?;
+        let addr = server.local_addr()?;
+        thread::spawn(move || server.run());
+
+        let mut alice = Client::connect(addr)?;
+        let mut bob = Client::connect(addr)?;
+
+        assert_eq!("ERR Join first", alice.request("CARD")?);
+        assert_eq!("OK", alice.request("JOIN alice")?);
+        assert_eq!("ERR Already joined", alice.request("JOIN alice")?);
+        assert_eq!("OK", bob.request("JOIN bob")?);
+        assert_eq!("CARD 1 2/3 4", alice.request("CARD")?);
+        assert_eq!("CARD 5 6/7 8", bob.request("CARD")?);
+
+        let mut carol = Client::connect(addr)?;
+        assert_eq!("ERR No cards left", carol.request("JOIN carol")?);
+        assert_eq!("BYE", carol.request("QUIT")?);
+
+        assert_eq!("ERR No bingo", alice.request("BINGO")?);
+
+        for (drawer, play) in [(0, 1), (1, 5), (1, 2)] {
+            let drawer = if drawer == 0 { &mut alice } else { &mut bob };
+            drawer.send("DRAW")?;
+
+            let expected = format!("DRAWN {}", play);
+            assert_eq!(expected, alice.recv()?);
+            assert_eq!(expected, bob.recv()?);
+        }
+
+        assert_eq!("ERR No bingo", bob.request("BINGO")?);
+
+        alice.send("BINGO")?;
+        assert_eq!("WINNER alice 14", alice.recv()?);
+        assert_eq!("WINNER alice 14", bob.recv()?);
+        assert_eq!("ERR Already claimed", alice.request("BINGO")?);
+
+        assert_eq!("ERR No plays left", bob.request("DRAW")?);
+        assert_eq!("ERR Unknown command: 'FLY'", bob.request("FLY")?);
+        assert_eq!("BYE", bob.request("QUIT")?);
+
+        // Bob's card is handed out again, already marked
+        let mut dave = Client::connect(addr)?;
+        assert_eq!("OK", dave.request("JOIN dave")?);
+        assert_eq!("CARD 5 6/7 8", dave.request("CARD")?);
+
+        // Alice's card has won, so it isn't
+        assert_eq!("BYE", alice.request("QUIT")?);
+        let mut erin = Client::connect(addr)?;
+        assert_eq!("ERR No cards left", erin.request("JOIN erin")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_slow_player() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let mut game = Game {
+            cards: vec![board(&["1 2", "3 4"])?],
+            plays: vec![],
+            drawn: 0,
+            players: BTreeMap::new(),
+            next_id: 0,
+        };
+
+        // Neither player reads anything, but only one falls behind
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let (fast, fast_receiver) = mpsc::sync_channel(QUEUE_LEN + 1);
+        let (slow, _slow_receiver) = mpsc::sync_channel(QUEUE_LEN);
+        let (mut clients, mut streams) = (Vec::new(), Vec::new());
+        for (name, sender) in [("fast", fast), ("slow", slow)] {
+            clients.push(TcpStream::connect(listener.local_addr()?)?);
+            let stream = listener.accept()?.0;
+            streams.push(stream.try_clone()?);
+
+            game.players.insert(
+                game.next_id,
+                Player {
+                    name: name.to_string(),
+                    board: board(&["5 6", "7 8"])?,
+                    sender,
+                    stream,
+                    claimed: false,
+                },
+            );
+            game.next_id += 1;
+        }
+
+        for i in 0..=QUEUE_LEN {
+            game.broadcast(&format!("DRAWN {}", i));
+        }
+
+        assert_eq!(vec![0], game.players.keys().copied().collect::<Vec<_>>());
+        assert_eq!(QUEUE_LEN + 1, fast_receiver.try_iter().count());
+        assert_eq!(2, game.cards.len());
+        assert_eq!(5, game.cards[0].spaces[0].value);
+
+        // The slow player's commands stop being read, which ends their connection rather than
+        // leaving it holding an id nobody knows
+        for stream in streams.iter() {
+            stream.set_read_timeout(Some(Duration::from_millis(100)))?;
+        }
+        assert!(streams[0].read(&mut [0; 1]).is_err());
+        assert_eq!(0, streams[1].read(&mut [0; 1])?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_loopback_only() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        assert!(Server::bind("0.0.0.0:0", vec![], vec![]).is_err());
+        assert!(Server::bind("127.0.0.1:0", vec![], vec![]).is_ok());
+
+        Ok(())
+    }
+}
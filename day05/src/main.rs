@@ -1,42 +1,59 @@
 use anyhow::{anyhow, Result};
 use log::{debug, trace, LevelFilter};
-use std::cmp::{max, min, Ordering};
+use std::cmp::{max, min};
+use std::collections::HashMap;
 use std::fmt;
+use std::fs;
 use std::str::FromStr;
+use sweep::SweepCounter;
+use vent_map::{Mode, VentMap};
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
-struct Point {
-    x: u32,
-    y: u32,
+mod svg;
+mod sweep;
+mod vent_map;
+
+/// A point with `N` coordinates, written `x,y` in 2D or `x,y,z` in 3D for volumetric data.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+struct Point<const N: usize = 2>([u32; N]);
+
+impl Point {
+    fn x(&self) -> u32 {
+        self.0[0]
+    }
+
+    fn y(&self) -> u32 {
+        self.0[1]
+    }
 }
 
-impl FromStr for Point {
+impl<const N: usize> FromStr for Point<N> {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (x, y) = s
-            .split_once(',')
-            .ok_or_else(|| anyhow!("Delimiter not found in {}", s))?;
+        let coords = s
+            .split(',')
+            .map(u32::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(Point {
-            x: u32::from_str(x)?,
-            y: u32::from_str(y)?,
-        })
+        Ok(Point(coords.try_into().map_err(|_| {
+            anyhow!("Expected {} coordinates in {}", N, s)
+        })?))
     }
 }
 
-impl fmt::Debug for Point {
+impl<const N: usize> fmt::Debug for Point<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({}, {})", self.x, self.y)
+        let coords = self.0.map(|c| c.to_string());
+        write!(f, "({})", coords.join(", "))
     }
 }
 
-struct Vent {
-    start: Point,
-    end: Point,
+struct Vent<const N: usize = 2> {
+    start: Point<N>,
+    end: Point<N>,
 }
 
-impl FromStr for Vent {
+impl<const N: usize> FromStr for Vent<N> {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -54,57 +71,178 @@ impl FromStr for Vent {
     }
 }
 
-impl fmt::Debug for Vent {
+/// How a vent covers the grid when it isn't horizontal, vertical or 45° diagonal. All modes
+/// agree on those vents.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Rasterization {
+    /// Rejects any other slope.
+    #[default]
+    Strict,
+    /// Only the lattice points exactly on the line.
+    Exact,
+    /// Bresenham's line, which covers one point per step along the longer axis.
+    Bresenham,
+}
+
+impl FromStr for Rasterization {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(Rasterization::Strict),
+            "exact" => Ok(Rasterization::Exact),
+            "bresenham" => Ok(Rasterization::Bresenham),
+            _ => Err(anyhow!("Unknown rasterization: '{}'", s)),
+        }
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl<const N: usize> Vent<N> {
+    fn deltas(&self) -> [i64; N] {
+        std::array::from_fn(|i| self.end.0[i] as i64 - self.start.0[i] as i64)
+    }
+
+    /// Whether the vent is axis-aligned or diagonal, i.e. every coordinate which changes does so
+    /// by the same amount.
+    fn is_axis_or_diagonal(&self) -> bool {
+        let deltas = self.deltas();
+        let len = deltas.iter().map(|d| d.abs()).max().unwrap_or(0);
+        deltas.iter().all(|d| *d == 0 || d.abs() == len)
+    }
+
+    /// Every point the vent covers, from start to end. The slope is checked up front, so the
+    /// points themselves are only generated as they're consumed.
+    fn points(&self, rasterization: Rasterization) -> Result<impl Iterator<Item = Point<N>>> {
+        let deltas = self.deltas();
+
+        if rasterization == Rasterization::Strict && !self.is_axis_or_diagonal() {
+            return Err(anyhow!("Vent isn't axis-aligned or diagonal: {:?}", self));
+        }
+
+        let steps = match rasterization {
+            // One point per step along the longest axis
+            Rasterization::Bresenham => deltas.iter().map(|d| d.abs()).max().unwrap_or(0),
+            // Lattice points are evenly spaced along the line, which also covers the other slopes
+            Rasterization::Strict | Rasterization::Exact => {
+                deltas.iter().fold(0, |g, d| gcd(g, d.abs()))
+            }
+        };
+
+        let start = self.start;
+        Ok((0..=steps).map(move |i| {
+            Point(std::array::from_fn(|c| {
+                (start.0[c] as i64 + offset(i, deltas[c], steps)) as u32
+            }))
+        }))
+    }
+}
+
+/// How far `i` of `steps` equal steps along a delta of `d` get, rounded to the nearest point with
+/// ties away from the start. That's exact for lattice points, and Bresenham's line otherwise.
+fn offset(i: i64, d: i64, steps: i64) -> i64 {
+    let (i, d, steps) = (i as i128, d as i128, max(steps, 1) as i128);
+    (d.signum() * ((2 * i * d.abs() + steps) / (2 * steps))) as i64
+}
+
+impl<const N: usize> fmt::Debug for Vent<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?} -> {:?}", self.start, self.end)
     }
 }
 
+/// Largest grid, in cells, which is allocated densely. Larger extents use a `SparseGrid`.
+const DENSE_CELL_LIMIT: u64 = 1 << 24;
+
+/// Counts how many vents cover each point.
+trait OverlapCounter<const N: usize = 2>: fmt::Debug {
+    fn track_vent(&mut self, vent: &Vent<N>) -> Result<()>;
+
+    /// Number of points covered by at least two vents.
+    fn overlap_ct(&self) -> usize;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Counter {
+    /// `Dense` or `Sparse`, depending on the vents' extent.
+    Auto,
+    Dense,
+    Sparse,
+    Sweep,
+}
+
+impl FromStr for Counter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Counter::Auto),
+            "dense" => Ok(Counter::Dense),
+            "sparse" => Ok(Counter::Sparse),
+            "sweep" => Ok(Counter::Sweep),
+            _ => Err(anyhow!("Unknown counter: '{}'", s)),
+        }
+    }
+}
+
+/// Picks a dense grid when the vents' extent is small enough to allocate, else a sparse one.
+fn overlap_counter(
+    counter: Counter,
+    rasterization: Rasterization,
+    vents: &[Vent],
+) -> Box<dyn OverlapCounter> {
+    let width = vents
+        .iter()
+        .map(|v| max(v.start.x(), v.end.x()))
+        .max()
+        .unwrap_or(0);
+    let height = vents
+        .iter()
+        .map(|v| max(v.start.y(), v.end.y()))
+        .max()
+        .unwrap_or(0);
+    debug!("Width: {}, Height: {}", width, height);
+
+    let dense = (width as u64 + 1) * (height as u64 + 1) <= DENSE_CELL_LIMIT;
+    match counter {
+        Counter::Auto if dense => Box::new(Grid::new(width, height, rasterization)),
+        Counter::Auto => Box::new(SparseGrid::new(rasterization)),
+        Counter::Dense => Box::new(Grid::new(width, height, rasterization)),
+        Counter::Sparse => Box::new(SparseGrid::new(rasterization)),
+        Counter::Sweep => Box::new(SweepCounter::default()),
+    }
+}
+
 struct Grid {
     inner: Vec<Vec<u32>>,
+    rasterization: Rasterization,
 }
 
 impl Grid {
-    fn new(width: u32, height: u32) -> Self {
+    fn new(width: u32, height: u32, rasterization: Rasterization) -> Self {
         Self {
             inner: vec![vec![0; width as usize + 1]; height as usize + 1],
+            rasterization,
         }
     }
+}
 
-    fn track_vent(&mut self, vent: &Vent) {
-        let (start, end) = (&vent.start, &vent.end);
-        if start.x == end.x {
-            let x = start.x as usize;
-            for y in start.y..=end.y {
-                self.inner[y as usize][x] += 1;
-            }
-        } else if start.y == end.y {
-            let y = start.y as usize;
-            for x in start.x..=end.x {
-                self.inner[y][x as usize] += 1;
-            }
-        } else {
-            let mut x = start.x;
-            let mut y = start.y;
-            while x != end.x && y != end.y {
-                self.inner[y as usize][x as usize] += 1;
-
-                match start.x.cmp(&end.x) {
-                    Ordering::Less => x += 1,
-                    Ordering::Greater => x -= 1,
-                    Ordering::Equal => (),
-                }
-
-                match start.y.cmp(&end.y) {
-                    Ordering::Less => y += 1,
-                    Ordering::Greater => y -= 1,
-                    Ordering::Equal => (),
-                }
-            }
-            self.inner[y as usize][x as usize] += 1;
+impl OverlapCounter for Grid {
+    fn track_vent(&mut self, vent: &Vent) -> Result<()> {
+        for point in vent.points(self.rasterization)? {
+            self.inner[point.y() as usize][point.x() as usize] += 1;
         }
         debug!("Applied: {:?}", vent);
         trace!("Grid: {:?}", self);
+
+        Ok(())
     }
 
     fn overlap_ct(&self) -> usize {
@@ -115,6 +253,37 @@ impl Grid {
     }
 }
 
+/// Only stores points which some vent covers, for vents spread over huge coordinates.
+#[derive(Debug, Default)]
+struct SparseGrid<const N: usize = 2> {
+    inner: HashMap<Point<N>, u32>,
+    rasterization: Rasterization,
+}
+
+impl<const N: usize> SparseGrid<N> {
+    fn new(rasterization: Rasterization) -> Self {
+        Self {
+            inner: HashMap::new(),
+            rasterization,
+        }
+    }
+}
+
+impl<const N: usize> OverlapCounter<N> for SparseGrid<N> {
+    fn track_vent(&mut self, vent: &Vent<N>) -> Result<()> {
+        for point in vent.points(self.rasterization)? {
+            *self.inner.entry(point).or_default() += 1;
+        }
+        debug!("Applied: {:?}", vent);
+
+        Ok(())
+    }
+
+    fn overlap_ct(&self) -> usize {
+        self.inner.values().filter(|v| **v > 1).count()
+    }
+}
+
 impl fmt::Debug for Grid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "{{")?;
@@ -136,44 +305,382 @@ impl fmt::Debug for Grid {
     }
 }
 
+/// Flags which only apply to vents in the plane, so are rejected with `--3d`.
+const PLANAR_FLAGS: [&str; 7] = [
+    "--vents",
+    "--counter",
+    "--rasterization",
+    "--svg",
+    "--highlight",
+    "--point",
+    "--top",
+];
+
+struct Options {
+    mode: Mode,
+    counter: Counter,
+    rasterization: Rasterization,
+    svg: Option<String>,
+    highlight: bool,
+    points: Vec<Point>,
+    top: Option<usize>,
+    three_d: bool,
+}
+
+impl Options {
+    /// Parses `[--vents axis|all] [--counter auto|dense|sparse|sweep]
+    /// [--rasterization strict|exact|bresenham] [--svg FILE [--highlight]] [--point X,Y]...
+    /// [--top K]`, or `--3d` for vents through 3D space.
+    fn parse<I: Iterator<Item = String>>(mut args: aoc_utils::Args<I>) -> Result<Self> {
+        let mut options = Self {
+            mode: Mode::All,
+            counter: Counter::Auto,
+            rasterization: Rasterization::Strict,
+            svg: None,
+            highlight: false,
+            points: Vec::new(),
+            top: None,
+            three_d: false,
+        };
+
+        let mut planar_flag = None;
+        while let Some(arg) = args.next() {
+            if PLANAR_FLAGS.contains(&arg.as_str()) {
+                planar_flag.get_or_insert_with(|| arg.clone());
+            }
+
+            match arg.as_str() {
+                "--vents" => options.mode = args.parse()?,
+                "--counter" => options.counter = args.parse()?,
+                "--rasterization" => options.rasterization = args.parse()?,
+                "--svg" => options.svg = Some(args.value()?),
+                "--highlight" => options.highlight = true,
+                "--point" => options.points.push(args.parse()?),
+                "--top" => options.top = Some(args.parse()?),
+                "--3d" => options.three_d = true,
+                _ => return Err(args.unexpected()),
+            }
+        }
+
+        if let Some(flag) = planar_flag.filter(|_| options.three_d) {
+            return Err(anyhow!("{} isn't supported with --3d", flag));
+        }
+        if options.counter == Counter::Sweep && options.rasterization != Rasterization::Strict {
+            return Err(anyhow!(
+                "--counter sweep only supports --rasterization strict, not {:?}",
+                options.rasterization
+            ));
+        }
+
+        Ok(options)
+    }
+}
+
 fn main() -> Result<()> {
     aoc_utils::init_logger(LevelFilter::Info)?;
 
+    let options = Options::parse(aoc_utils::args())?;
+
+    if options.three_d {
+        let mut grid = SparseGrid::<3>::default();
+        for line in aoc_utils::input()? {
+            grid.track_vent(&Vent::from_str(&line)?)?;
+        }
+
+        println!("{}", grid.overlap_ct());
+        return Ok(());
+    }
+
     let vents = aoc_utils::input()?
         .into_iter()
-        .map(|l| Vent::from_str(&l).ok())
-        .flatten()
+        .map(|l| Vent::from_str(&l))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|v| options.mode.includes(v))
         .collect::<Vec<_>>();
 
-    assert!(!vents.is_empty());
-
-    let width = vents.iter().map(|v| max(v.start.x, v.end.x)).max().unwrap();
-    let height = vents.iter().map(|v| max(v.start.y, v.end.y)).max().unwrap();
-    debug!("Width: {}, Height: {}", width, height);
+    if vents.is_empty() {
+        return Err(anyhow!("No vents to count with {:?}", options.mode));
+    }
 
-    let mut grid = Grid::new(width, height);
+    let mut grid = overlap_counter(options.counter, options.rasterization, &vents);
     for vent in vents.iter() {
-        grid.track_vent(vent);
+        grid.track_vent(vent)?;
     }
 
     debug!("Grid: {:?}", grid);
 
     println!("{}", grid.overlap_ct());
 
+    if let Some(path) = options.svg {
+        fs::write(
+            path,
+            svg::svg(&vents, options.rasterization, options.highlight)?,
+        )?;
+    }
+
+    if !options.points.is_empty() || options.top.is_some() {
+        let map = VentMap::new(vents, options.mode, options.rasterization)?;
+        debug!(
+            "Mapped {} vents with {} overlaps",
+            map.vents().len(),
+            map.overlap_ct()
+        );
+
+        for point in options.points {
+            println!(
+                "{:?}: {} {:?}",
+                point,
+                map.coverage(point),
+                map.vents_at(point)
+            );
+        }
+        for (point, ct) in map.top_k(options.top.unwrap_or(0)) {
+            println!("{:?}: {}", point, ct);
+        }
+    }
+
     Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use aoc_utils::Args;
 
     #[test]
     fn test_parse_vent() -> Result<()> {
         aoc_utils::init_test_logger()?;
 
-        let vent = Vent::from_str("0,9 -> 5,9")?;
+        let vent: Vent = Vent::from_str("0,9 -> 5,9")?;
         debug!("{:?}", vent);
 
+        // A 3D vent isn't silently read as a 2D one
+        assert_eq!(
+            "Expected 2 coordinates in 0,0,0",
+            Vent::<2>::from_str("0,0,0 -> 2,2,2")
+                .unwrap_err()
+                .to_string()
+        );
+
+        Ok(())
+    }
+
+    pub(crate) fn example() -> Vec<Vent> {
+        [
+            "0,9 -> 5,9",
+            "8,0 -> 0,8",
+            "9,4 -> 3,4",
+            "2,2 -> 2,1",
+            "7,0 -> 7,4",
+            "6,4 -> 2,0",
+            "0,9 -> 2,9",
+            "3,4 -> 1,4",
+            "0,0 -> 8,8",
+            "5,5 -> 8,2",
+        ]
+        .iter()
+        .map(|s| Vent::from_str(s).unwrap())
+        .collect()
+    }
+
+    fn overlap_ct(mut counter: impl OverlapCounter, vents: &[Vent]) -> Result<usize> {
+        for vent in vents {
+            counter.track_vent(vent)?;
+        }
+        Ok(counter.overlap_ct())
+    }
+
+    fn vents(vents: &[&str]) -> Result<Vec<Vent>> {
+        vents.iter().map(|s| Vent::from_str(s)).collect()
+    }
+
+    #[test]
+    fn test_counters_agree() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let vents = example();
+        assert_eq!(
+            12,
+            overlap_ct(Grid::new(9, 9, Rasterization::Strict), &vents)?
+        );
+        assert_eq!(12, overlap_ct(SparseGrid::default(), &vents)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_huge_coordinates() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let vents = vents(&[
+            "1000000,5 -> 1000000,20",
+            "999990,10 -> 1000010,10",
+            "0,0 -> 3,3",
+        ])?;
+
+        let mut counter = overlap_counter(Counter::Auto, Rasterization::Strict, &vents);
+        for vent in vents.iter() {
+            counter.track_vent(vent)?;
+        }
+        assert_eq!(1, counter.overlap_ct());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rasterization() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let points = |vent: &str, rasterization| -> Result<Vec<(u32, u32)>> {
+            Ok(Vent::from_str(vent)?
+                .points(rasterization)?
+                .map(|p| (p.x(), p.y()))
+                .collect())
+        };
+
+        for rasterization in [
+            Rasterization::Strict,
+            Rasterization::Exact,
+            Rasterization::Bresenham,
+        ] {
+            assert_eq!(
+                vec![(1, 3), (2, 2), (3, 1)],
+                points("3,1 -> 1,3", rasterization)?
+            );
+            assert_eq!(vec![(4, 4)], points("4,4 -> 4,4", rasterization)?);
+        }
+
+        assert!(points("0,0 -> 4,2", Rasterization::Strict).is_err());
+        assert_eq!(
+            vec![(0, 0), (2, 1), (4, 2)],
+            points("0,0 -> 4,2", Rasterization::Exact)?
+        );
+        assert_eq!(
+            vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)],
+            points("0,0 -> 4,2", Rasterization::Bresenham)?
+        );
+        assert_eq!(
+            vec![(0, 6), (1, 4), (2, 2), (3, 0)],
+            points("3,0 -> 0,6", Rasterization::Exact)?
+        );
+
+        // Points are generated lazily, so a long vent doesn't build them all up front
+        let long = Vent::from_str("0,0 -> 4000000000,4000000000")?;
+        assert_eq!(
+            vec![(0, 0), (1, 1)],
+            long.points(Rasterization::Strict)?
+                .take(2)
+                .map(|p| (p.x(), p.y()))
+                .collect::<Vec<_>>()
+        );
+
+        let vents = vents(&["0,0 -> 4,2", "0,1 -> 4,1", "2,0 -> 2,4"])?;
+        assert!(overlap_ct(SparseGrid::new(Rasterization::Strict), &vents).is_err());
+        assert_eq!(
+            1,
+            overlap_ct(SparseGrid::new(Rasterization::Exact), &vents)?
+        );
+        assert_eq!(
+            2,
+            overlap_ct(Grid::new(4, 4, Rasterization::Bresenham), &vents)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_options() -> Result<()> {
+        let options = Options::parse(Args::from_line("--counter sparse --rasterization exact"))?;
+        assert_eq!(Counter::Sparse, options.counter);
+        assert_eq!(Rasterization::Exact, options.rasterization);
+
+        assert!(Options::parse(Args::from_line("--counter sweep")).is_ok());
+        assert!(
+            Options::parse(Args::from_line("--counter sweep --rasterization bresenham")).is_err()
+        );
+        assert!(Options::parse(Args::from_line("--rasterization exact --counter sweep")).is_err());
+
+        assert!(Options::parse(Args::from_line("--3d"))?.three_d);
+        assert!(Options::parse(Args::from_line("--3d --vents axis")).is_err());
+        assert!(Options::parse(Args::from_line("--top 3 --3d")).is_err());
+
+        Ok(())
+    }
+
+    fn overlap_ct_3d(vents: &[&str]) -> Result<usize> {
+        let mut grid = SparseGrid::<3>::default();
+        for vent in vents {
+            grid.track_vent(&Vent::from_str(vent)?)?;
+        }
+        Ok(grid.overlap_ct())
+    }
+
+    #[test]
+    fn test_parse_3d() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let vent = Vent::<3>::from_str("3,2,1 -> 0,2,4")?;
+        assert_eq!("(0, 2, 4) -> (3, 2, 1)", format!("{:?}", vent));
+
+        assert!(Vent::<3>::from_str("0,0,0 => 1,1,1").is_err());
+        assert!(Vent::<3>::from_str("0,0 -> 1,1,1").is_err());
+        assert!(Vent::<3>::from_str("0,0,x -> 1,1,1").is_err());
+        assert!(Vent::<3>::from_str("0,0,0,0 -> 1,1,1").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_points_3d() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let points = |vent: &str| -> Result<Vec<[u32; 3]>> {
+            Ok(Vent::<3>::from_str(vent)?
+                .points(Rasterization::Strict)?
+                .map(|p| p.0)
+                .collect())
+        };
+
+        assert_eq!(vec![[1, 1, 1], [1, 1, 2]], points("1,1,2 -> 1,1,1")?);
+        assert_eq!(
+            vec![[0, 2, 2], [1, 1, 1], [2, 0, 0]],
+            points("2,0,0 -> 0,2,2")?
+        );
+        assert_eq!(vec![[0, 0, 5], [1, 1, 5]], points("0,0,5 -> 1,1,5")?);
+        assert!(points("0,0,0 -> 1,2,3").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_overlaps_3d() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        // The 2D example, in the z = 0 plane
+        let example = example()
+            .iter()
+            .map(|v| {
+                format!(
+                    "{},{},0 -> {},{},0",
+                    v.start.x(),
+                    v.start.y(),
+                    v.end.x(),
+                    v.end.y()
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            12,
+            overlap_ct_3d(&example.iter().map(|s| s.as_str()).collect::<Vec<_>>())?
+        );
+
+        assert_eq!(
+            2,
+            overlap_ct_3d(&["0,0,0 -> 4,4,4", "2,2,0 -> 2,2,4", "0,4,4 -> 4,4,4"])?
+        );
+        assert!(overlap_ct_3d(&["0,0,0 -> 2,1,0"]).is_err());
+
         Ok(())
     }
 }
@@ -0,0 +1,224 @@
+use super::{Point, Rasterization, Vent};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Longest side of the plot, in pixels.
+const PLOT_SIZE: f64 = 800.0;
+const MARGIN: f64 = 20.0;
+const LEGEND_WIDTH: f64 = 160.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Orientation {
+    Horizontal,
+    Vertical,
+    Diagonal,
+    Other,
+}
+
+impl Orientation {
+    const ALL: [Orientation; 4] = [
+        Orientation::Horizontal,
+        Orientation::Vertical,
+        Orientation::Diagonal,
+        Orientation::Other,
+    ];
+
+    fn of(vent: &Vent) -> Self {
+        let [dx, dy] = vent.deltas();
+        if dy == 0 {
+            Orientation::Horizontal
+        } else if dx == 0 {
+            Orientation::Vertical
+        } else if dx.abs() == dy.abs() {
+            Orientation::Diagonal
+        } else {
+            Orientation::Other
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Orientation::Horizontal => "horizontal",
+            Orientation::Vertical => "vertical",
+            Orientation::Diagonal => "diagonal",
+            Orientation::Other => "other",
+        }
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            Orientation::Horizontal => "steelblue",
+            Orientation::Vertical => "seagreen",
+            Orientation::Diagonal => "darkorange",
+            Orientation::Other => "purple",
+        }
+    }
+}
+
+/// Draws each vent over a heatmap of how many vents cover each point, with y increasing
+/// downwards. When `highlight` is set, points counted by `overlap_ct` are circled.
+pub fn svg(vents: &[Vent], rasterization: Rasterization, highlight: bool) -> Result<String> {
+    let mut counts: HashMap<Point, u32> = HashMap::new();
+    for vent in vents {
+        for point in vent.points(rasterization)? {
+            *counts.entry(point).or_default() += 1;
+        }
+    }
+
+    let points = || vents.iter().flat_map(|v| [v.start, v.end]);
+    let min_x = points().map(|p| p.x()).min().unwrap_or(0) as f64;
+    let max_x = points().map(|p| p.x()).max().unwrap_or(0) as f64;
+    let min_y = points().map(|p| p.y()).min().unwrap_or(0) as f64;
+    let max_y = points().map(|p| p.y()).max().unwrap_or(0) as f64;
+
+    // Each point is a cell, so the plot spans one more than the extent
+    let scale = PLOT_SIZE / (max_x - min_x + 1.0).max(max_y - min_y + 1.0);
+    let (plot_width, plot_height) = ((max_x - min_x + 1.0) * scale, (max_y - min_y + 1.0) * scale);
+    let (width, height) = (
+        plot_width + LEGEND_WIDTH + 3.0 * MARGIN,
+        plot_height.max(120.0) + 2.0 * MARGIN,
+    );
+
+    let x = |x: u32| MARGIN + (x as f64 - min_x) * scale;
+    let y = |y: u32| MARGIN + (y as f64 - min_y) * scale;
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="0 0 {:.0} {:.0}">"#,
+        width, height, width, height
+    )?;
+    writeln!(
+        out,
+        r#"<rect width="{:.0}" height="{:.0}" fill="white"/>"#,
+        width, height
+    )?;
+
+    // Heatmap, sorted so the output is stable
+    let max_count = counts.values().copied().max().unwrap_or(0);
+    let mut cells = counts.iter().collect::<Vec<_>>();
+    cells.sort_unstable_by_key(|(p, _)| (p.y(), p.x()));
+
+    writeln!(out, r#"<g id="heatmap" fill="crimson">"#)?;
+    for (point, count) in cells.iter() {
+        writeln!(
+            out,
+            r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill-opacity="{:.2}"/>"#,
+            x(point.x()),
+            y(point.y()),
+            scale,
+            scale,
+            **count as f64 / max_count as f64
+        )?;
+    }
+    writeln!(out, "</g>")?;
+
+    writeln!(
+        out,
+        r#"<g id="vents" stroke-width="{:.2}">"#,
+        (scale / 4.0).max(0.5)
+    )?;
+    for vent in vents {
+        writeln!(
+            out,
+            r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}"/>"#,
+            x(vent.start.x()) + scale / 2.0,
+            y(vent.start.y()) + scale / 2.0,
+            x(vent.end.x()) + scale / 2.0,
+            y(vent.end.y()) + scale / 2.0,
+            Orientation::of(vent).color()
+        )?;
+    }
+    writeln!(out, "</g>")?;
+
+    if highlight {
+        writeln!(
+            out,
+            r#"<g id="overlaps" fill="none" stroke="black" stroke-width="{:.2}">"#,
+            (scale / 8.0).max(0.5)
+        )?;
+        for (point, _) in cells.iter().filter(|(_, count)| **count > 1) {
+            writeln!(
+                out,
+                r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}"/>"#,
+                x(point.x()) + scale / 2.0,
+                y(point.y()) + scale / 2.0,
+                (scale / 2.0).max(1.5)
+            )?;
+        }
+        writeln!(out, "</g>")?;
+    }
+
+    // Legend
+    let legend_x = plot_width + 2.0 * MARGIN;
+    let mut entries = Orientation::ALL
+        .iter()
+        .map(|o| (o.color(), o.name().to_string()))
+        .collect::<Vec<_>>();
+    entries.push(("crimson", format!("covered 1..{} times", max_count)));
+
+    writeln!(out, r#"<g id="legend" font-size="12">"#)?;
+    for (i, (color, label)) in entries.iter().enumerate() {
+        let entry_y = MARGIN + 18.0 * i as f64;
+        writeln!(
+            out,
+            r#"<rect x="{:.0}" y="{:.0}" width="12" height="12" fill="{}"/>"#,
+            legend_x, entry_y, color
+        )?;
+        writeln!(
+            out,
+            r#"<text x="{:.0}" y="{:.0}">{}</text>"#,
+            legend_x + 18.0,
+            entry_y + 10.0,
+            label
+        )?;
+    }
+    if highlight {
+        let entry_y = MARGIN + 18.0 * entries.len() as f64;
+        writeln!(
+            out,
+            r#"<circle cx="{:.0}" cy="{:.0}" r="5" fill="none" stroke="black"/>"#,
+            legend_x + 6.0,
+            entry_y + 6.0
+        )?;
+        writeln!(
+            out,
+            r#"<text x="{:.0}" y="{:.0}">overlaps: {}</text>"#,
+            legend_x + 18.0,
+            entry_y + 10.0,
+            counts.values().filter(|count| **count > 1).count()
+        )?;
+    }
+    writeln!(out, "</g>")?;
+
+    writeln!(out, "</svg>")?;
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::example;
+
+    #[test]
+    fn test_svg() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let svg = svg(&example(), Rasterization::Strict, true)?;
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(10, svg.matches("<line").count());
+        assert_eq!(4, svg.matches(r#"stroke="darkorange"/>"#).count());
+        assert!(svg.contains("covered 1..3 times"));
+        assert!(svg.contains("overlaps: 12"));
+        assert_eq!(12 + 1, svg.matches("<circle").count());
+
+        let plain = super::svg(&example(), Rasterization::Strict, false)?;
+        assert_eq!(0, plain.matches("<circle").count());
+
+        Ok(())
+    }
+}
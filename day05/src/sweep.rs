@@ -0,0 +1,364 @@
+//! Counts overlaps between horizontal, vertical and 45° vents without visiting their points.
+//!
+//! A point is covered twice either by two vents on the same line, or by vents on different lines
+//! crossing there. Vents on each line are reduced to the intervals covered at least twice, and
+//! crossings are found by sweeping vents in order of x so only vents with overlapping x ranges
+//! are compared. Crossings already inside a doubled interval aren't counted again, and neither
+//! are points where doubled intervals on different lines cross.
+
+use super::{OverlapCounter, Vent};
+use anyhow::{anyhow, Result};
+use log::debug;
+use std::cmp::{max, min};
+use std::collections::{HashMap, HashSet};
+
+/// Direction of a line, each of which is identified by a key constant along it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Family {
+    /// Key is y, parameterized by x.
+    Horizontal,
+    /// Key is x, parameterized by y.
+    Vertical,
+    /// y increases with x. Key is y - x, parameterized by x.
+    Rising,
+    /// y decreases as x increases. Key is x + y, parameterized by x.
+    Falling,
+}
+
+const FAMILIES: [Family; 4] = [
+    Family::Horizontal,
+    Family::Vertical,
+    Family::Rising,
+    Family::Falling,
+];
+
+impl Family {
+    fn key(&self, (x, y): (i64, i64)) -> i64 {
+        match self {
+            Family::Horizontal => y,
+            Family::Vertical => x,
+            Family::Rising => y - x,
+            Family::Falling => x + y,
+        }
+    }
+
+    fn param(&self, (x, y): (i64, i64)) -> i64 {
+        match self {
+            Family::Vertical => y,
+            _ => x,
+        }
+    }
+
+    /// Coefficients `(a, b)` of the line `a*x + b*y = key`.
+    fn coefficients(&self) -> (i64, i64) {
+        match self {
+            Family::Horizontal => (0, 1),
+            Family::Vertical => (1, 0),
+            Family::Rising => (-1, 1),
+            Family::Falling => (1, 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    family: Family,
+    key: i64,
+    lo: i64,
+    hi: i64,
+}
+
+impl Segment {
+    fn new(vent: &Vent) -> Result<Self> {
+        let start = (vent.start.x() as i64, vent.start.y() as i64);
+        let end = (vent.end.x() as i64, vent.end.y() as i64);
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+
+        let family = if dy == 0 {
+            Family::Horizontal
+        } else if dx == 0 {
+            Family::Vertical
+        } else if dx == dy {
+            Family::Rising
+        } else if dx == -dy {
+            Family::Falling
+        } else {
+            return Err(anyhow!(
+                "The sweep counter only supports horizontal, vertical and diagonal vents: {:?}",
+                vent
+            ));
+        };
+
+        let (a, b) = (family.param(start), family.param(end));
+        Ok(Self {
+            family,
+            key: family.key(start),
+            lo: min(a, b),
+            hi: max(a, b),
+        })
+    }
+
+    fn point(&self, param: i64) -> (i64, i64) {
+        match self.family {
+            Family::Horizontal => (param, self.key),
+            Family::Vertical => (self.key, param),
+            Family::Rising => (param, self.key + param),
+            Family::Falling => (param, self.key - param),
+        }
+    }
+
+    fn x_range(&self) -> (i64, i64) {
+        (self.point(self.lo).0, self.point(self.hi).0)
+    }
+
+    fn y_range(&self) -> (i64, i64) {
+        let (a, b) = (self.point(self.lo).1, self.point(self.hi).1);
+        (min(a, b), max(a, b))
+    }
+
+    fn contains(&self, point: (i64, i64)) -> bool {
+        self.family.key(point) == self.key
+            && (self.lo..=self.hi).contains(&self.family.param(point))
+    }
+
+    /// The lattice point where this crosses a segment of another family, if any.
+    fn crossing(&self, other: &Segment) -> Option<(i64, i64)> {
+        let (a1, b1) = self.family.coefficients();
+        let (a2, b2) = other.family.coefficients();
+
+        let det = a1 * b2 - a2 * b1;
+        if det == 0 {
+            return None;
+        }
+
+        let x = self.key * b2 - other.key * b1;
+        let y = a1 * other.key - a2 * self.key;
+        if x % det != 0 || y % det != 0 {
+            return None;
+        }
+
+        let point = (x / det, y / det);
+        (self.contains(point) && other.contains(point)).then_some(point)
+    }
+}
+
+/// Parts of the line covered by at least two of `intervals`, merged and in order.
+fn doubled(mut intervals: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    intervals.sort_unstable();
+
+    let mut doubled: Vec<(i64, i64)> = Vec::new();
+    let mut reach: Option<i64> = None;
+
+    for (lo, hi) in intervals {
+        if let Some(reach) = reach.filter(|reach| lo <= *reach) {
+            let (lo, hi) = (lo, min(hi, reach));
+            match doubled.last_mut() {
+                Some(last) if lo <= last.1 + 1 => last.1 = max(last.1, hi),
+                _ => doubled.push((lo, hi)),
+            }
+        }
+
+        reach = Some(reach.map_or(hi, |reach| max(reach, hi)));
+    }
+
+    doubled
+}
+
+/// Lattice points where segments of different families cross, found by sweeping them in order of
+/// x so only segments with overlapping x ranges are compared.
+fn crossings(segments: &[Segment]) -> HashSet<(i64, i64)> {
+    let mut segments = segments.to_vec();
+    segments.sort_unstable_by_key(|s| s.x_range());
+
+    let mut crossings = HashSet::new();
+    let mut comparisons = 0;
+    for (i, segment) in segments.iter().enumerate() {
+        let (_, max_x) = segment.x_range();
+        let (min_y, max_y) = segment.y_range();
+
+        for other in segments[i + 1..]
+            .iter()
+            .take_while(|other| other.x_range().0 <= max_x)
+        {
+            comparisons += 1;
+
+            let (other_min_y, other_max_y) = other.y_range();
+            if other.family == segment.family || other_max_y < min_y || other_min_y > max_y {
+                continue;
+            }
+
+            if let Some(point) = segment.crossing(other) {
+                crossings.insert(point);
+            }
+        }
+    }
+    debug!(
+        "Compared {} of {} segment pairs",
+        comparisons,
+        segments.len() * segments.len().saturating_sub(1) / 2
+    );
+
+    crossings
+}
+
+#[derive(Debug, Default)]
+pub struct SweepCounter {
+    segments: Vec<Segment>,
+}
+
+impl OverlapCounter for SweepCounter {
+    fn track_vent(&mut self, vent: &Vent) -> Result<()> {
+        self.segments.push(Segment::new(vent)?);
+        Ok(())
+    }
+
+    fn overlap_ct(&self) -> usize {
+        let mut lines: HashMap<(Family, i64), Vec<(i64, i64)>> = HashMap::new();
+        for segment in self.segments.iter() {
+            lines
+                .entry((segment.family, segment.key))
+                .or_default()
+                .push((segment.lo, segment.hi));
+        }
+
+        let lines = lines
+            .into_iter()
+            .map(|(line, intervals)| (line, doubled(intervals)))
+            .filter(|(_, doubled)| !doubled.is_empty())
+            .collect::<HashMap<_, _>>();
+
+        // How many lines cover the point with a doubled interval
+        let doubled_ct = |point: (i64, i64)| {
+            FAMILIES
+                .iter()
+                .filter(|family| {
+                    lines
+                        .get(&(**family, family.key(point)))
+                        .is_some_and(|doubled| {
+                            let param = family.param(point);
+                            let i = doubled.partition_point(|(_, hi)| *hi < param);
+                            doubled.get(i).is_some_and(|(lo, _)| *lo <= param)
+                        })
+                })
+                .count()
+        };
+
+        let crossing_ct = crossings(&self.segments)
+            .into_iter()
+            .filter(|point| doubled_ct(*point) == 0)
+            .count();
+
+        let collinear = lines
+            .values()
+            .flatten()
+            .map(|(lo, hi)| (hi - lo + 1) as usize)
+            .sum::<usize>();
+
+        // Points where doubled intervals cross were counted once for each of their lines
+        let doubled_segments = lines
+            .iter()
+            .flat_map(|(&(family, key), doubled)| {
+                doubled.iter().map(move |&(lo, hi)| Segment {
+                    family,
+                    key,
+                    lo,
+                    hi,
+                })
+            })
+            .collect::<Vec<_>>();
+        let recounted = crossings(&doubled_segments)
+            .into_iter()
+            .map(|point| doubled_ct(point) - 1)
+            .sum::<usize>();
+
+        collinear - recounted + crossing_ct
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::example;
+    use crate::{Point, SparseGrid};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::str::FromStr;
+
+    fn overlap_ct(mut counter: impl OverlapCounter, vents: &[Vent]) -> Result<usize> {
+        for vent in vents {
+            counter.track_vent(vent)?;
+        }
+        Ok(counter.overlap_ct())
+    }
+
+    #[test]
+    fn test_doubled() {
+        assert_eq!(Vec::<(i64, i64)>::new(), doubled(vec![(0, 3), (4, 6)]));
+        assert_eq!(vec![(2, 3)], doubled(vec![(0, 3), (2, 6)]));
+        assert_eq!(vec![(1, 9)], doubled(vec![(0, 10), (1, 4), (3, 9)]));
+        assert_eq!(vec![(2, 2), (5, 5)], doubled(vec![(5, 8), (0, 2), (2, 5)]));
+    }
+
+    #[test]
+    fn test_example() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        assert_eq!(12, overlap_ct(SweepCounter::default(), &example())?);
+
+        // Doubled intervals on two lines share a point, which is only counted once
+        let crossed = ["0,5 -> 10,5", "2,5 -> 8,5", "3,0 -> 3,10", "3,1 -> 3,9"]
+            .iter()
+            .map(|s| Vent::from_str(s))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(15, overlap_ct(SweepCounter::default(), &crossed)?);
+        assert!(overlap_ct(SweepCounter::default(), &[Vent::from_str("0,0 -> 2,1")?]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches_rasterizing() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let mut rng = StdRng::seed_from_u64(2021);
+        let vent = |start: Point, (dx, dy): (i64, i64), len: u32| {
+            let end = Point([
+                (start.x() as i64 + dx * len as i64) as u32,
+                (start.y() as i64 + dy * len as i64) as u32,
+            ]);
+
+            Vent {
+                start: min(start, end),
+                end: max(start, end),
+            }
+        };
+
+        for _ in 0..50 {
+            let mut vents = Vec::new();
+            for _ in 0..30 {
+                let start = Point([rng.gen_range(20..50), rng.gen_range(20..50)]);
+                let direction = [(1, 0), (0, 1), (1, 1), (1, -1)][rng.gen_range(0..4)];
+                let len = rng.gen_range(0..12);
+                vents.push(vent(start, direction, len));
+
+                // Often overlap it with another vent on the same line, so doubled intervals of
+                // different families cross
+                if rng.gen_bool(0.5) {
+                    let offset = rng.gen_range(0..=len);
+                    let start = Point([
+                        (start.x() as i64 + direction.0 * offset as i64) as u32,
+                        (start.y() as i64 + direction.1 * offset as i64) as u32,
+                    ]);
+                    vents.push(vent(start, direction, rng.gen_range(0..8)));
+                }
+            }
+
+            assert_eq!(
+                overlap_ct(SparseGrid::default(), &vents)?,
+                overlap_ct(SweepCounter::default(), &vents)?
+            );
+        }
+
+        Ok(())
+    }
+}
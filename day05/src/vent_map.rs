@@ -0,0 +1,148 @@
+use super::{Point, Rasterization, Vent};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Which vents to consider: part one only counts horizontal and vertical vents.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    AxisAligned,
+    #[default]
+    All,
+}
+
+impl Mode {
+    pub fn includes(&self, vent: &Vent) -> bool {
+        let [dx, dy] = vent.deltas();
+        *self == Mode::All || dx == 0 || dy == 0
+    }
+}
+
+impl FromStr for Mode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "axis" => Ok(Mode::AxisAligned),
+            "all" => Ok(Mode::All),
+            _ => Err(anyhow!("Unknown vent mode: '{}'", s)),
+        }
+    }
+}
+
+/// Vents indexed by the points they cover, for queries about individual points.
+#[derive(Debug)]
+pub struct VentMap {
+    vents: Vec<Vent>,
+    /// Indices of the vents covering each point.
+    coverage: HashMap<Point, Vec<usize>>,
+}
+
+impl VentMap {
+    /// Maps the vents `mode` includes, skipping the rest.
+    pub fn new(vents: Vec<Vent>, mode: Mode, rasterization: Rasterization) -> Result<Self> {
+        let vents = vents
+            .into_iter()
+            .filter(|v| mode.includes(v))
+            .collect::<Vec<_>>();
+
+        let mut coverage: HashMap<Point, Vec<usize>> = HashMap::new();
+        for (i, vent) in vents.iter().enumerate() {
+            for point in vent.points(rasterization)? {
+                coverage.entry(point).or_default().push(i);
+            }
+        }
+
+        Ok(Self { vents, coverage })
+    }
+
+    pub fn vents(&self) -> &[Vent] {
+        &self.vents
+    }
+
+    /// Number of vents covering `point`.
+    pub fn coverage(&self, point: Point) -> usize {
+        self.coverage.get(&point).map_or(0, |vents| vents.len())
+    }
+
+    /// Vents covering `point`, in input order.
+    pub fn vents_at(&self, point: Point) -> Vec<&Vent> {
+        self.coverage.get(&point).map_or_else(Vec::new, |vents| {
+            vents.iter().map(|i| &self.vents[*i]).collect()
+        })
+    }
+
+    /// The `k` most covered points with their coverage, most covered first and ties in point
+    /// order.
+    pub fn top_k(&self, k: usize) -> Vec<(Point, usize)> {
+        let mut points = self
+            .coverage
+            .iter()
+            .map(|(point, vents)| (*point, vents.len()))
+            .collect::<Vec<_>>();
+        points.sort_unstable_by_key(|(point, ct)| (std::cmp::Reverse(*ct), *point));
+        points.truncate(k);
+        points
+    }
+
+    /// Number of points covered by at least two vents.
+    pub fn overlap_ct(&self) -> usize {
+        self.coverage
+            .values()
+            .filter(|vents| vents.len() > 1)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::example;
+
+    fn point(x: u32, y: u32) -> Point {
+        Point([x, y])
+    }
+
+    #[test]
+    fn test_modes() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let axis = VentMap::new(example(), Mode::AxisAligned, Rasterization::Strict)?;
+        assert_eq!(6, axis.vents().len());
+        assert_eq!(5, axis.overlap_ct());
+        assert_eq!(1, axis.coverage(point(4, 4)));
+
+        let all = VentMap::new(example(), Mode::All, Rasterization::Strict)?;
+        assert_eq!(10, all.vents().len());
+        assert_eq!(12, all.overlap_ct());
+        assert_eq!(3, all.coverage(point(4, 4)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_queries() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let map = VentMap::new(example(), Mode::All, Rasterization::Strict)?;
+
+        assert_eq!(0, map.coverage(point(9, 9)));
+        assert!(map.vents_at(point(9, 9)).is_empty());
+
+        assert_eq!(
+            vec!["(0, 8) -> (8, 0)", "(3, 4) -> (9, 4)", "(0, 0) -> (8, 8)"],
+            map.vents_at(point(4, 4))
+                .iter()
+                .map(|v| format!("{:?}", v))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            vec![(point(4, 4), 3), (point(6, 4), 3), (point(0, 9), 2)],
+            map.top_k(3)
+        );
+        assert_eq!(map.coverage.len(), map.top_k(usize::MAX).len());
+
+        Ok(())
+    }
+}
@@ -1,35 +1,59 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use aoc_utils::Automaton;
 use log::{debug, trace, LevelFilter};
+use matrix::{Exact, Modular};
 use std::str::FromStr;
 
+mod matrix;
+
 #[derive(Default, Debug, PartialEq, Eq)]
 struct Fish {
     inner: [u64; 9],
+    step_ct: usize,
 }
 
 impl Fish {
     const BIRTH_INTERVAL: usize = 7;
 
-    fn advance(&mut self) {
-        trace!("Pre:  {:?}, count={}", self.inner, self.count());
+    fn count(&self) -> u64 {
+        self.inner.iter().sum()
+    }
 
+    /// Steps like `step`, unless the population would no longer fit in a u64, in which case
+    /// nothing changes and `None` is returned.
+    fn checked_step(&mut self) -> Option<()> {
         let to_birth = self.inner[0];
 
-        for i in 0..(self.inner.len() - 1) {
-            self.inner[i] = self.inner[i + 1];
-        }
+        // Create new fish at the last position
+        let mut inner = self.inner;
+        inner.rotate_left(1);
 
         // Reset fish which just gave birth
-        self.inner[Self::BIRTH_INTERVAL - 1] += to_birth;
+        inner[Self::BIRTH_INTERVAL - 1] = inner[Self::BIRTH_INTERVAL - 1].checked_add(to_birth)?;
 
-        // Create new fish at the last position
-        self.inner[self.inner.len() - 1] = to_birth;
+        inner
+            .iter()
+            .try_fold(0u64, |sum, ct| sum.checked_add(*ct))?;
+
+        self.inner = inner;
+        self.step_ct += 1;
+        Some(())
+    }
+}
+
+impl Automaton for Fish {
+    /// Panics once the population no longer fits in a u64, see `checked_step`.
+    fn step(&mut self) {
+        trace!("Pre:  {:?}, count={}", self.inner, self.count());
+
+        self.checked_step()
+            .expect("The population no longer fits in a u64");
 
         debug!("Post: {:?}, count={}", self.inner, self.count());
     }
 
-    fn count(&self) -> u64 {
-        self.inner.iter().sum()
+    fn step_ct(&self) -> usize {
+        self.step_ct
     }
 }
 
@@ -47,19 +71,151 @@ impl FromStr for Fish {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Method {
+    /// Steps through every day, which fails once the population overflows a little past day 400.
+    Step,
+    /// Exponentiates the transition matrix, see `matrix`.
+    Matrix,
+}
+
+impl FromStr for Method {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "step" => Ok(Method::Step),
+            "matrix" => Ok(Method::Matrix),
+            _ => Err(anyhow!("Unknown method: '{}'", s)),
+        }
+    }
+}
+
+struct Options {
+    days: u64,
+    method: Method,
+    modulus: Option<u64>,
+}
+
+impl Options {
+    /// Parses `[DAYS] [--method step|matrix] [--mod P]`, where counting modulo `P` uses the
+    /// matrix method. Counting exactly is limited to `matrix::EXACT_DAY_LIMIT` days.
+    fn parse<I: Iterator<Item = String>>(mut args: aoc_utils::Args<I>) -> Result<Self> {
+        let mut options = Self {
+            days: 256,
+            method: Method::Matrix,
+            modulus: None,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--method" => options.method = args.parse()?,
+                "--mod" => {
+                    options.modulus = Some(args.parse()?);
+                    options.method = Method::Matrix;
+                }
+                _ if !arg.starts_with("--") => options.days = u64::from_str(&arg)?,
+                _ => return Err(args.unexpected()),
+            }
+        }
+
+        if options.method == Method::Matrix
+            && options.modulus.is_none()
+            && options.days > matrix::EXACT_DAY_LIMIT
+        {
+            return Err(anyhow!(
+                "Exact counts are limited to {} days, count modulo P with --mod P instead",
+                matrix::EXACT_DAY_LIMIT
+            ));
+        }
+
+        Ok(options)
+    }
+}
+
 fn main() -> Result<()> {
     aoc_utils::init_logger(LevelFilter::Info)?;
 
+    let options = Options::parse(aoc_utils::args())?;
+
     let lines = aoc_utils::input()?;
     assert_eq!(1, lines.len());
 
     let mut fish = Fish::from_str(&lines[0])?;
 
-    for _ in 0..256 {
-        fish.advance();
+    match (options.method, options.modulus) {
+        (Method::Step, Some(_)) => return Err(anyhow!("Counting modulo P needs --method matrix")),
+        (Method::Step, None) => {
+            for _ in 0..options.days {
+                // With no fish left, nothing changes however many days remain
+                if fish.count() == 0 {
+                    break;
+                }
+
+                fish.checked_step().ok_or_else(|| {
+                    anyhow!(
+                        "The population overflows a u64 on day {}, use --method matrix",
+                        fish.step_ct() + 1
+                    )
+                })?;
+            }
+            println!("{}", fish.count());
+        }
+        (Method::Matrix, None) => {
+            println!("{}", matrix::population(&fish, options.days, &Exact));
+        }
+        (Method::Matrix, Some(modulus)) => {
+            let modular = Modular::new(modulus)?;
+            println!("{}", matrix::population(&fish, options.days, &modular));
+        }
     }
 
-    println!("{}", fish.count());
-
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aoc_utils::Args;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_options() -> Result<()> {
+        // Stepping overflows well before the days the matrix handles
+        let options = Options::parse(Args::from_line("500"))?;
+        assert_eq!((500, Method::Matrix), (options.days, options.method));
+        assert_eq!(
+            Method::Step,
+            Options::parse(Args::from_line("--method step"))?.method
+        );
+
+        assert!(Options::parse(Args::from_line("1000000")).is_ok());
+        assert!(Options::parse(Args::from_line("1000001")).is_err());
+        assert_eq!(
+            Some(7),
+            Options::parse(Args::from_line("1000000000 --mod 7"))?.modulus
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_checked_step() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let example = || Fish::from_str("3,4,3,1,2");
+        let mut fish = example()?;
+        while fish.checked_step().is_some() {}
+
+        // Stepping stops on the last day whose population fits, without losing any fish
+        let days = fish.step_ct() as u64;
+        assert!((400..500).contains(&days));
+        assert_eq!(
+            BigUint::from(fish.count()),
+            matrix::population(&example()?, days, &Exact)
+        );
+        assert!(matrix::population(&example()?, days + 1, &Exact) > BigUint::from(u64::MAX));
+
+        Ok(())
+    }
+}
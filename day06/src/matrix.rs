@@ -0,0 +1,202 @@
+//! Population after any number of days, by raising the daily transition matrix to that power
+//! with repeated squaring, taking O(log days) matrix products.
+//!
+//! Exact counts grow by roughly an eighth of a bit per day, so they're limited to
+//! `EXACT_DAY_LIMIT` days; beyond that, count modulo some number instead.
+
+use super::Fish;
+use anyhow::{anyhow, Result};
+use num_bigint::BigUint;
+use std::array;
+
+const TIMERS: usize = 9;
+
+/// Most days counted exactly, where the count is around 125,000 bits and takes about a second.
+pub const EXACT_DAY_LIMIT: u64 = 1_000_000;
+
+type Matrix<T> = [[T; TIMERS]; TIMERS];
+
+/// The numbers counts are kept in.
+pub trait Arithmetic<T> {
+    fn zero(&self) -> T;
+    fn one(&self) -> T;
+    fn add(&self, a: &T, b: &T) -> T;
+    fn mul(&self, a: &T, b: &T) -> T;
+}
+
+pub struct Exact;
+
+impl Arithmetic<BigUint> for Exact {
+    fn zero(&self) -> BigUint {
+        BigUint::ZERO
+    }
+
+    fn one(&self) -> BigUint {
+        BigUint::from(1u8)
+    }
+
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a + b
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b
+    }
+}
+
+/// Counts modulo a number, multiplying in u128 so nothing overflows.
+pub struct Modular(u64);
+
+impl Modular {
+    pub fn new(modulus: u64) -> Result<Self> {
+        if modulus == 0 {
+            return Err(anyhow!("Modulus must be positive"));
+        }
+        Ok(Self(modulus))
+    }
+}
+
+impl Arithmetic<u64> for Modular {
+    fn zero(&self) -> u64 {
+        0
+    }
+
+    fn one(&self) -> u64 {
+        1 % self.0
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> u64 {
+        ((*a as u128 + *b as u128) % self.0 as u128) as u64
+    }
+
+    fn mul(&self, a: &u64, b: &u64) -> u64 {
+        ((*a as u128 * *b as u128) % self.0 as u128) as u64
+    }
+}
+
+fn multiply<T, A: Arithmetic<T>>(a: &Matrix<T>, b: &Matrix<T>, arith: &A) -> Matrix<T> {
+    array::from_fn(|i| {
+        array::from_fn(|j| {
+            (0..TIMERS).fold(arith.zero(), |sum, k| {
+                arith.add(&sum, &arith.mul(&a[i][k], &b[k][j]))
+            })
+        })
+    })
+}
+
+/// Entry `[i][j]` is how many fish with timer `i` one fish with timer `j` becomes in a day.
+fn transition<T, A: Arithmetic<T>>(arith: &A) -> Matrix<T> {
+    array::from_fn(|i| {
+        array::from_fn(|j| {
+            let births = j == 0 && (i == Fish::BIRTH_INTERVAL - 1 || i == TIMERS - 1);
+            if births || j == i + 1 {
+                arith.one()
+            } else {
+                arith.zero()
+            }
+        })
+    })
+}
+
+fn power<T, A: Arithmetic<T>>(mut base: Matrix<T>, mut exp: u64, arith: &A) -> Matrix<T> {
+    let mut result: Matrix<T> =
+        array::from_fn(|i| array::from_fn(|j| if i == j { arith.one() } else { arith.zero() }));
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = multiply(&result, &base, arith);
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = multiply(&base, &base, arith);
+        }
+    }
+
+    result
+}
+
+/// Number of fish after `days` more days.
+pub fn population<T, A: Arithmetic<T>>(fish: &Fish, days: u64, arith: &A) -> T
+where
+    T: From<u64>,
+{
+    let days = power(transition(arith), days, arith);
+
+    // The counts only matter summed, so sum each column first
+    (0..TIMERS).fold(arith.zero(), |sum, j| {
+        let column = (0..TIMERS).fold(arith.zero(), |sum, i| arith.add(&sum, &days[i][j]));
+        let fish = arith.add(&arith.zero(), &T::from(fish.inner[j]));
+        arith.add(&sum, &arith.mul(&column, &fish))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aoc_utils::Automaton;
+    use std::str::FromStr;
+
+    fn example() -> Fish {
+        Fish::from_str("3,4,3,1,2").unwrap()
+    }
+
+    #[test]
+    fn test_example() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let fish = example();
+        assert_eq!(BigUint::from(26u8), population(&fish, 18, &Exact));
+        assert_eq!(BigUint::from(5934u16), population(&fish, 80, &Exact));
+        assert_eq!(
+            BigUint::from(26984457539u64),
+            population(&fish, 256, &Exact)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches_stepping() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let mut fish = example();
+        for days in 0..=300 {
+            assert_eq!(
+                BigUint::from(fish.count()),
+                population(&example(), days, &Exact),
+                "{} days",
+                days
+            );
+            fish.step();
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_modular() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let p = 1_000_000_007;
+        let modular = Modular::new(p)?;
+
+        let exact = population(&example(), 5000, &Exact);
+        assert_eq!(
+            exact % BigUint::from(p),
+            BigUint::from(population(&example(), 5000, &modular))
+        );
+
+        // Large moduli must not overflow while multiplying
+        let modular = Modular::new(u64::MAX - 58)?;
+        let exact = population(&example(), 1000, &Exact);
+        assert_eq!(
+            exact % BigUint::from(u64::MAX - 58),
+            BigUint::from(population(&example(), 1000, &modular))
+        );
+
+        assert!(population(&example(), 1_000_000_000_000, &Modular::new(p)?) < p);
+        assert!(Modular::new(0).is_err());
+
+        Ok(())
+    }
+}
@@ -117,3 +117,35 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_basins() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let input = vec![
+            "2199943210",
+            "3987894921",
+            "9856789892",
+            "8767896789",
+            "9899965678",
+        ]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+        let height_map = HeightMap::try_from(input)?;
+        aoc_utils::assert_snapshot("height_map", format!("{:?}", height_map))?;
+
+        assert_eq!(Some(3), height_map.basin_size(1, 0));
+        assert_eq!(Some(9), height_map.basin_size(9, 0));
+        assert_eq!(Some(14), height_map.basin_size(2, 2));
+        assert_eq!(Some(9), height_map.basin_size(6, 4));
+        assert_eq!(None, height_map.basin_size(0, 0));
+
+        Ok(())
+    }
+}
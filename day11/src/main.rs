@@ -1,13 +1,16 @@
 use anyhow::{anyhow, Result};
+use aoc_utils::{Automaton, Debuggable};
 use log::{trace, LevelFilter};
 use std::fmt;
+use std::io;
 use std::str::FromStr;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct Octopuses {
     grid: Vec<Vec<usize>>,
     step_ct: usize,
     flash_ct: usize,
+    last_flash_ct: usize,
 }
 
 impl Octopuses {
@@ -16,10 +19,17 @@ impl Octopuses {
             grid: vec![vec![0; 10]; 10],
             step_ct: 0,
             flash_ct: 0,
+            last_flash_ct: 0,
         }
     }
 
-    fn step(&mut self) -> bool {
+    fn all_flashed(&self) -> bool {
+        self.last_flash_ct == 100
+    }
+}
+
+impl Automaton for Octopuses {
+    fn step(&mut self) {
         self.step_ct += 1;
 
         trace!("Step {}", self.step_ct);
@@ -66,8 +76,50 @@ impl Octopuses {
         }
 
         self.flash_ct += flashes;
+        self.last_flash_ct = flashes;
+    }
 
-        flashes == 100
+    fn step_ct(&self) -> usize {
+        self.step_ct
+    }
+}
+
+impl Debuggable for Octopuses {
+    fn get(&self, x: usize, y: usize) -> Result<String> {
+        self.grid
+            .get(y)
+            .and_then(|r| r.get(x))
+            .map(|v| v.to_string())
+            .ok_or_else(|| anyhow!("Out of bounds: ({}, {})", x, y))
+    }
+
+    fn set(&mut self, x: usize, y: usize, value: &str) -> Result<()> {
+        let value = usize::from_str(value)?;
+        if value > 9 {
+            return Err(anyhow!("Energy level must be at most 9: {}", value));
+        }
+
+        let space = self
+            .grid
+            .get_mut(y)
+            .and_then(|r| r.get_mut(x))
+            .ok_or_else(|| anyhow!("Out of bounds: ({}, {})", x, y))?;
+        *space = value;
+
+        Ok(())
+    }
+
+    fn condition(&self, condition: &str) -> Result<bool> {
+        if condition == "all_flashed" {
+            Ok(self.all_flashed())
+        } else if let Some(ct) = condition.strip_prefix("flash_ct ") {
+            Ok(self.flash_ct >= usize::from_str(ct)?)
+        } else {
+            Err(anyhow!(
+                "Unknown condition: '{}', expected 'all_flashed' or 'flash_ct <n>'",
+                condition
+            ))
+        }
     }
 }
 
@@ -116,13 +168,26 @@ fn main() -> Result<()> {
 
     let mut octopuses = Octopuses::try_from(aoc_utils::input()?)?;
 
-    loop {
-        if octopuses.step() {
-            println!("{}", octopuses.step_ct);
-            break;
+    // `debug` steps through the simulation interactively instead of solving it
+    let mut debug = false;
+    let mut args = aoc_utils::args();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "debug" => debug = true,
+            _ => return Err(args.unexpected()),
         }
     }
 
+    if debug {
+        aoc_utils::debug_repl(octopuses, io::stdin().lock(), io::stdout())?;
+        return Ok(());
+    }
+
+    let step = octopuses
+        .step_until(Octopuses::all_flashed)
+        .ok_or_else(|| anyhow!("The octopuses never all flash"))?;
+    println!("{}", step);
+
     Ok(())
 }
 
@@ -130,122 +195,28 @@ fn main() -> Result<()> {
 mod test {
     use super::*;
 
-    impl PartialEq<&'static str> for Octopuses {
-        fn eq(&self, other: &&'static str) -> bool {
-            for (y, line) in other.lines().map(|l| l.trim()).enumerate() {
-                for (x, c) in line.chars().enumerate() {
-                    let val = usize::from_str(&c.to_string()).unwrap();
-                    if val != self.grid[y][x] {
-                        return false;
-                    }
-                }
-            }
-            true
-        }
-    }
-
     #[test]
     fn test_steps() -> Result<()> {
         aoc_utils::init_test_logger()?;
 
         let mut octopuses = Octopuses::try_from(aoc_utils::test_input()?)?;
 
-        octopuses.step();
-
-        assert_eq!(
-            octopuses,
-            r#"6594254334
-            3856965822
-            6375667284
-            7252447257
-            7468496589
-            5278635756
-            3287952832
-            7993992245
-            5957959665
-            6394862637"#
-        );
-
-        octopuses.step();
-
-        assert_eq!(
-            octopuses,
-            r#"8807476555
-            5089087054
-            8597889608
-            8485769600
-            8700908800
-            6600088989
-            6800005943
-            0000007456
-            9000000876
-            8700006848"#
-        );
-
-        octopuses.step();
-
-        assert_eq!(
-            octopuses,
-            r#"0050900866
-            8500800575
-            9900000039
-            9700000041
-            9935080063
-            7712300000
-            7911250009
-            2211130000
-            0421125000
-            0021119000"#
-        );
-
-        // Step 4
-        octopuses.step();
-
-        assert_eq!(
-            octopuses,
-            r#"2263031977
-            0923031697
-            0032221150
-            0041111163
-            0076191174
-            0053411122
-            0042361120
-            5532241122
-            1532247211
-            1132230211"#
-        );
-
-        // Step 10
-        for _ in 4..10 {
+        for step in 1..=4 {
             octopuses.step();
+            aoc_utils::assert_snapshot(&format!("step_{}", step), format!("{:?}", octopuses))?;
         }
 
-        assert_eq!(
-            octopuses,
-            r#"0481112976
-            0031112009
-            0041112504
-            0081111406
-            0099111306
-            0093511233
-            0442361130
-            5532252350
-            0532250600
-            0032240000"#
-        );
+        // Step 10
+        octopuses.step_n(6);
+
+        aoc_utils::assert_snapshot("step_10", format!("{:?}", octopuses))?;
         assert_eq!(204, octopuses.flash_ct);
 
-        for _ in 10..100 {
-            octopuses.step();
-        }
+        octopuses.step_n(90);
         assert_eq!(1656, octopuses.flash_ct);
 
-        for _ in 100..194 {
-            octopuses.step();
-        }
-
-        // Step 195 is an all-flash step
-        assert!(octopuses.step());
+        // Step 195 is the first all-flash step
+        assert_eq!(Some(195), octopuses.step_until(Octopuses::all_flashed));
 
         Ok(())
     }
@@ -1,4 +1,5 @@
 use anyhow::{anyhow, Result};
+use aoc_utils::Automaton;
 use log::{debug, info, LevelFilter};
 use std::fmt;
 use std::str::FromStr;
@@ -13,29 +14,41 @@ enum Fold {
 struct Transparency {
     grid: Vec<Vec<bool>>,
     folds: Vec<Fold>,
+    step_ct: usize,
 }
 
 impl Transparency {
-    fn fold(&mut self) {
+    fn count_visible(&self) -> usize {
+        self.grid
+            .iter()
+            .map(|r| r.iter().filter(|b| **b).count())
+            .sum()
+    }
+}
+
+impl Automaton for Transparency {
+    /// Applies the next fold, if any remain.
+    fn step(&mut self) {
         if let Some(fold) = self.folds.pop() {
+            self.step_ct += 1;
+
             match fold {
+                // The fold line itself is dropped, and whatever lies past it is mirrored onto
+                // the other side
                 Fold::Vertical(at) => {
-                    let mut bottom = self.grid.get_mut(at..).unwrap().to_vec();
-                    bottom.reverse();
+                    let bottom = self.grid.drain(at..).skip(1).collect::<Vec<_>>();
 
-                    for (y, row) in bottom.iter().enumerate() {
+                    for (i, row) in bottom.iter().enumerate().take(at) {
                         for (x, val) in row.iter().enumerate() {
-                            self.grid[y][x] |= *val;
+                            self.grid[at - 1 - i][x] |= *val;
                         }
                     }
-
-                    self.grid.truncate(at);
                 }
                 Fold::Horizontal(at) => {
                     for row in self.grid.iter_mut() {
-                        let right = row.drain(at + 1..).collect::<Vec<_>>();
-                        for (i, val) in right.iter().rev().enumerate() {
-                            row[i] |= *val;
+                        let right = row.drain(at..).skip(1).collect::<Vec<_>>();
+                        for (i, val) in right.iter().enumerate().take(at) {
+                            row[at - 1 - i] |= *val;
                         }
                     }
                 }
@@ -43,11 +56,12 @@ impl Transparency {
         }
     }
 
-    fn count_visible(&self) -> usize {
-        self.grid
-            .iter()
-            .map(|r| r.iter().filter(|b| **b).count())
-            .sum()
+    fn step_ct(&self) -> usize {
+        self.step_ct
+    }
+
+    fn is_done(&self) -> bool {
+        self.folds.is_empty()
     }
 }
 
@@ -112,7 +126,7 @@ impl fmt::Debug for Transparency {
             )?;
         }
         writeln!(f, "}}")?;
-        writeln!(f, "")?;
+        writeln!(f)?;
 
         let mut folds = self.folds.clone();
         folds.reverse();
@@ -124,9 +138,95 @@ fn main() -> Result<()> {
     aoc_utils::init_logger(LevelFilter::Info)?;
 
     let mut transparency = Transparency::try_from(aoc_utils::input()?)?;
-    transparency.fold();
+    transparency.step();
     debug!("{:?}", transparency);
     info!("Count: {}", transparency.count_visible());
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_folds() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let input = vec![
+            "6,10",
+            "0,14",
+            "9,10",
+            "0,3",
+            "10,4",
+            "4,11",
+            "6,0",
+            "6,12",
+            "4,1",
+            "0,13",
+            "10,12",
+            "3,4",
+            "3,0",
+            "8,4",
+            "1,10",
+            "2,14",
+            "8,10",
+            "9,0",
+            "",
+            "fold along y=7",
+            "fold along x=5",
+        ]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+        let mut transparency = Transparency::try_from(input)?;
+        aoc_utils::assert_snapshot("initial", format!("{:?}", transparency))?;
+
+        transparency.step();
+        aoc_utils::assert_snapshot("fold_1", format!("{:?}", transparency))?;
+        assert_eq!(17, transparency.count_visible());
+
+        transparency.step();
+        aoc_utils::assert_snapshot("fold_2", format!("{:?}", transparency))?;
+        assert_eq!(16, transparency.count_visible());
+
+        // Out of folds
+        assert!(transparency.is_done());
+        assert_eq!(0, transparency.step_n(1));
+        assert_eq!(None, transparency.step_until(|t| t.count_visible() == 0));
+        assert_eq!(2, transparency.step_ct());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fold_line() -> Result<()> {
+        aoc_utils::init_test_logger()?;
+
+        let input = ["0,0", "4,1", "1,4", "", "fold along x=3", "fold along y=3"]
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        let mut transparency = Transparency::try_from(input)?;
+        let rows = |t: &Transparency| {
+            format!("{:?}", t)
+                .lines()
+                .skip(1)
+                .take_while(|l| *l != "}")
+                .map(|l| l.trim().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // The fold line is dropped, and points past it are mirrored around it rather than from
+        // the far edge: x=4 lands on x=2, and y=4 on y=2
+        transparency.step();
+        assert_eq!(vec!["#..", "..#", "...", "...", ".#."], rows(&transparency));
+
+        transparency.step();
+        assert_eq!(vec!["#..", "..#", ".#."], rows(&transparency));
+        assert_eq!(3, transparency.count_visible());
+
+        Ok(())
+    }
+}
@@ -1,16 +1,23 @@
 use anyhow::{anyhow, Result};
+use aoc_utils::{Automaton, Debuggable};
 use log::{debug, trace, LevelFilter};
 use std::collections::HashMap;
+use std::io;
+use std::str::FromStr;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Manual {
     original_polymer: String,
     polymer: HashMap<String, usize>,
     insertion_rules: HashMap<String, String>,
+    step_ct: usize,
 }
 
-impl Manual {
+impl Automaton for Manual {
     fn step(&mut self) {
+        self.step_ct += 1;
+        debug!("Step {}", self.step_ct);
+
         let mut new_polymer = HashMap::new();
 
         for (pair, ct) in self.polymer.iter() {
@@ -28,16 +35,27 @@ impl Manual {
         trace!("Next: {:?}", self.polymer);
     }
 
-    fn step_by(&mut self, by: usize) {
-        for s in 0..by {
-            debug!("Step {}", s + 1);
-            self.step();
+    fn step_ct(&self) -> usize {
+        self.step_ct
+    }
+}
+
+impl Debuggable for Manual {
+    fn condition(&self, condition: &str) -> Result<bool> {
+        if let Some(len) = condition.strip_prefix("len ") {
+            Ok(self.polymer_len() >= usize::from_str(len)?)
+        } else {
+            Err(anyhow!(
+                "Unknown condition: '{}', expected 'len <n>'",
+                condition
+            ))
         }
     }
+}
 
-    #[cfg(test)]
+impl Manual {
     fn polymer_len(&self) -> usize {
-        self.polymer.iter().map(|(_, ct)| ct).sum::<usize>() + 1
+        self.polymer.values().sum::<usize>() + 1
     }
 
     fn counts(&self) -> HashMap<char, usize> {
@@ -91,6 +109,7 @@ impl TryFrom<Vec<String>> for Manual {
             original_polymer,
             polymer,
             insertion_rules,
+            step_ct: 0,
         })
     }
 }
@@ -99,7 +118,23 @@ fn main() -> Result<()> {
     aoc_utils::init_logger(LevelFilter::Info)?;
 
     let mut manual = Manual::try_from(aoc_utils::input()?)?;
-    manual.step_by(40);
+
+    // `debug` steps through the simulation interactively instead of solving it
+    let mut debug = false;
+    let mut args = aoc_utils::args();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "debug" => debug = true,
+            _ => return Err(args.unexpected()),
+        }
+    }
+
+    if debug {
+        aoc_utils::debug_repl(manual, io::stdin().lock(), io::stdout())?;
+        return Ok(());
+    }
+
+    manual.step_n(40);
 
     let counts = manual.counts();
     debug!("{:?}", counts);
@@ -122,10 +157,10 @@ mod test {
 
         let mut manual = Manual::try_from(aoc_utils::test_input()?)?;
 
-        manual.step_by(5);
+        manual.step_n(5);
         assert_eq!(97, manual.polymer_len());
 
-        manual.step_by(5);
+        manual.step_n(5);
         assert_eq!(3073, manual.polymer_len());
 
         let counts = manual.counts();